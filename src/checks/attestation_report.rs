@@ -0,0 +1,67 @@
+use std::{env, path::PathBuf};
+
+use colored::Colorize;
+use poi_radio_e2e_tests::{
+    reports::{read_report, RowKind},
+    utils::RadioRuntimeConfig,
+    MessagesArc,
+};
+use tracing::{debug, info};
+
+use crate::setup::test_radio::run_test_radio;
+
+fn report_path() -> PathBuf {
+    env::temp_dir().join(format!("radio-report-{}.json", std::process::id()))
+}
+
+fn success_handler(_messages: MessagesArc) {
+    // The report is written right before the success handler runs
+    let rows = read_report(&report_path()).unwrap();
+    debug!("Report rows: {:?}", rows);
+
+    for row in &rows {
+        assert!(
+            row.subgraph.is_empty() == (row.kind == RowKind::DeliveryLatency),
+            "Only delivery latency rows go without a subgraph"
+        );
+        match row.kind {
+            RowKind::LocalAttestation => {
+                assert!(
+                    !row.network.is_empty(),
+                    "Local attestation without a network"
+                );
+                assert!(row.npoi.is_some());
+            }
+            RowKind::RemoteAttestation => {
+                assert!(row.npoi.is_some() && row.stake_weight.is_some());
+                assert!(row.senders.unwrap_or_default() > 0);
+            }
+            RowKind::Comparison => {
+                assert!(!row.network.is_empty(), "Comparison without a network");
+                assert!(row.outcome.is_some());
+            }
+            RowKind::DeliveryLatency => {
+                assert!(row.samples.unwrap_or_default() > 0);
+                assert!(row.latency_p50_ms <= row.latency_p95_ms);
+                assert!(row.latency_p95_ms <= row.latency_p99_ms);
+            }
+        }
+    }
+
+    let has = |kind: RowKind| rows.iter().any(|row| row.kind == kind);
+    if has(RowKind::LocalAttestation)
+        && has(RowKind::RemoteAttestation)
+        && has(RowKind::Comparison)
+        && has(RowKind::DeliveryLatency)
+    {
+        info!("{}", "attestation_report test is sucessful ✅".green());
+        std::process::exit(0);
+    }
+}
+
+#[tokio::main]
+pub async fn run_attestation_report() {
+    let mut config = RadioRuntimeConfig::new(false, true);
+    config.report_path = Some(report_path());
+    run_test_radio(&config, success_handler).await;
+}
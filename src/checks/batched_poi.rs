@@ -0,0 +1,71 @@
+use crate::setup::constants::{
+    MOCK_SUBGRAPH_GOERLI, MOCK_SUBGRAPH_GOERLI_2, MOCK_SUBGRAPH_MAINNET,
+};
+use colored::Colorize;
+use poi_radio_e2e_tests::{
+    utils::{poi_requests, RadioRuntimeConfig},
+    MessagesArc, SENT_MESSAGES,
+};
+use std::collections::HashMap;
+use tracing::{debug, info};
+
+use crate::setup::test_radio::run_test_radio;
+
+fn success_handler(_messages: MessagesArc) {
+    let sent = SENT_MESSAGES.get().unwrap().lock().unwrap();
+
+    // NPOIs sent for the two goerli deployments, which always fall due at the same block
+    let mut goerli_pois: HashMap<u64, HashMap<&str, &str>> = HashMap::new();
+    for msg in sent
+        .iter()
+        .filter(|m| m.identifier == MOCK_SUBGRAPH_GOERLI || m.identifier == MOCK_SUBGRAPH_GOERLI_2)
+    {
+        goerli_pois
+            .entry(msg.block_number)
+            .or_default()
+            .insert(&msg.identifier, &msg.npoi);
+    }
+    debug!("Goerli POIs per block: {:?}", goerli_pois);
+
+    let batched_blocks = goerli_pois
+        .iter()
+        .filter(|(_, pois)| pois.len() == 2)
+        .inspect(|(block, pois)| {
+            assert!(
+                pois.values().all(|poi| *poi == pois[MOCK_SUBGRAPH_GOERLI]),
+                "Batched query returned different POIs for the same mock deployment: {pois:?}"
+            );
+
+            // Mainnet can fall due at the same block number and has its POI fetched in a request of its own, and
+            // the mock block number wrapping around can make the goerli deployments due at a block again
+            let sends = |identifier: &str| {
+                sent.iter()
+                    .filter(|m| m.identifier == identifier && m.block_number == **block)
+                    .count() as u64
+            };
+            let expected = sends(MOCK_SUBGRAPH_GOERLI) + sends(MOCK_SUBGRAPH_MAINNET);
+            assert_eq!(
+                poi_requests(**block),
+                expected,
+                "Expected one POI request per round at block {block}, the goerli deployments weren't batched"
+            );
+        })
+        .count();
+
+    if batched_blocks >= 3 {
+        info!("{}", "batched_poi test is sucessful ✅".green());
+        std::process::exit(0);
+    }
+}
+
+#[tokio::main]
+pub async fn run_batched_poi() {
+    // Every deployment after the first indexes goerli, so the last two are fetched in one request
+    let mut config = RadioRuntimeConfig::new(false, true);
+    config.subgraphs = Some(vec![
+        MOCK_SUBGRAPH_MAINNET.to_string(),
+        MOCK_SUBGRAPH_GOERLI.to_string(),
+        MOCK_SUBGRAPH_GOERLI_2.to_string(),
+    ]);
+    run_test_radio(&config, success_handler).await;
+}
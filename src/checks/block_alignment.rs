@@ -0,0 +1,79 @@
+use colored::Colorize;
+use poi_radio_e2e_tests::{utils::RadioRuntimeConfig, MessagesArc, NetworkName, NETWORKS};
+use std::collections::{HashMap, HashSet};
+use tracing::{debug, info};
+
+use crate::setup::test_radio::run_test_radio;
+
+/// Consecutive intervals a sender needs to have attested on before a network counts as verified
+const MIN_STEPS: usize = 3;
+
+fn success_handler(messages: MessagesArc) {
+    let messages = messages.lock().unwrap();
+
+    // Blocks each sender attested on for each deployment, in the order they arrived
+    let mut runs: HashMap<(String, String), (NetworkName, u64, Vec<u64>)> = HashMap::new();
+    for (sender, msg) in messages.iter() {
+        let network = NetworkName::from_string(&msg.network);
+        let interval = NETWORKS
+            .iter()
+            .find(|n| n.name == network)
+            .unwrap_or_else(|| panic!("Received a message on unregistered network {network}"))
+            .interval;
+        assert_eq!(
+            msg.block_number % interval,
+            0,
+            "Block {} on {} is not aligned to the network's interval of {}",
+            msg.block_number,
+            network,
+            interval
+        );
+
+        let (_, _, blocks) = runs
+            .entry((sender.clone(), msg.identifier.clone()))
+            .or_insert_with(|| (network, interval, vec![]));
+        // The radio can attest to the same block more than once while the chainhead is still inside the interval
+        if blocks.last() != Some(&msg.block_number) {
+            blocks.push(msg.block_number);
+        }
+    }
+
+    let mut verified: HashSet<NetworkName> = HashSet::new();
+    for ((sender, identifier), (network, interval, blocks)) in runs {
+        let mut steps = 0;
+        for pair in blocks.windows(2) {
+            // The sender's mock wrapped around, start counting again
+            if pair[1] < pair[0] {
+                steps = 0;
+                continue;
+            }
+            assert_eq!(
+                pair[1] - pair[0],
+                interval,
+                "{} skipped an interval on {} for {}: {:?}",
+                sender,
+                network,
+                identifier,
+                blocks
+            );
+            steps += 1;
+        }
+
+        if steps >= MIN_STEPS {
+            verified.insert(network);
+        }
+    }
+    debug!("Networks with aligned attestations: {:?}", verified);
+
+    if verified.contains(&NetworkName::Goerli) && verified.contains(&NetworkName::Mainnet) {
+        info!("{}", "block_alignment test is sucessful ✅".green());
+        std::process::exit(0);
+    }
+}
+
+#[tokio::main]
+pub async fn run_block_alignment() {
+    // The default deployments index mainnet (interval 4) and goerli (interval 2)
+    let config = RadioRuntimeConfig::new(false, true);
+    run_test_radio(&config, success_handler).await;
+}
@@ -0,0 +1,151 @@
+use crate::setup::constants::{
+    MOCK_CUSTOM_NETWORK, MOCK_CUSTOM_NETWORK_INTERVAL, MOCK_SUBGRAPH_CUSTOM, MOCK_SUBGRAPH_MAINNET,
+};
+use colored::Colorize;
+use poi_radio_e2e_tests::{
+    utils::{MockDeployment, MockSender, RadioRuntimeConfig},
+    MessagesArc, NetworkName, LAST_ROUND, NETWORKS, SENT_MESSAGES,
+};
+use std::{collections::HashSet, env};
+use tracing::{debug, info};
+
+use crate::setup::{
+    load::{own_boot_node_address, start_publishers},
+    test_radio::run_test_radio,
+};
+
+/// Attestations the publisher broadcasts per second
+const PUBLISH_RATE: f64 = 0.5;
+const PUBLISHER_STAKE: &str = "100000000000000000000000";
+
+/// Attestations on the custom network go out as unknown with the chain named in their payload, so they have to
+/// arrive under the custom network's name and be compared against our own
+fn success_handler(messages: MessagesArc) {
+    let custom_network = NetworkName::from_string(MOCK_CUSTOM_NETWORK);
+
+    for (_, message) in messages.lock().unwrap().iter() {
+        assert!(
+            NetworkName::from_string(&message.network) != NetworkName::Unknown,
+            "Received an attestation for {} on an unknown network",
+            message.identifier
+        );
+        if message.identifier == MOCK_SUBGRAPH_CUSTOM {
+            assert_eq!(
+                NetworkName::from_string(&message.network),
+                custom_network,
+                "Received an attestation for {} on the wrong network",
+                MOCK_SUBGRAPH_CUSTOM
+            );
+        }
+    }
+
+    for sent in SENT_MESSAGES.get().unwrap().lock().unwrap().iter() {
+        if sent.identifier != MOCK_SUBGRAPH_CUSTOM {
+            continue;
+        }
+        assert_eq!(
+            sent.network, custom_network,
+            "Message for {} was sent on the wrong network",
+            MOCK_SUBGRAPH_CUSTOM
+        );
+        assert_eq!(
+            sent.block_number % MOCK_CUSTOM_NETWORK_INTERVAL,
+            0,
+            "Block {} is not aligned to the custom network interval",
+            sent.block_number
+        );
+    }
+
+    let round = LAST_ROUND.get().unwrap().lock().unwrap();
+    let (remote, local) = match (
+        round.remote.get(MOCK_SUBGRAPH_CUSTOM),
+        round.local.get(MOCK_SUBGRAPH_CUSTOM),
+    ) {
+        (Some(remote), Some(local)) => (remote, local),
+        _ => {
+            debug!(
+                "No attestations for {} to compare yet",
+                MOCK_SUBGRAPH_CUSTOM
+            );
+            return;
+        }
+    };
+
+    let compared = local
+        .iter()
+        .filter_map(|(block, attestation)| {
+            let remote = remote.get(block)?;
+            assert!(
+                remote.iter().any(|remote| remote.npoi == attestation.npoi),
+                "Remote attestations for {} on block {} don't match ours",
+                MOCK_SUBGRAPH_CUSTOM,
+                block
+            );
+            assert_eq!(
+                round
+                    .networks
+                    .get(&(MOCK_SUBGRAPH_CUSTOM.to_string(), *block))
+                    .map(|network| NetworkName::from_string(network)),
+                Some(custom_network.clone()),
+                "Attestations for {} on block {} were aggregated under another network",
+                MOCK_SUBGRAPH_CUSTOM,
+                block
+            );
+            Some(*block)
+        })
+        .collect::<HashSet<u64>>();
+    debug!("Blocks compared on {}: {:?}", MOCK_CUSTOM_NETWORK, compared);
+
+    if compared.len() >= 3 {
+        info!("{}", "custom_network test is sucessful ✅".green());
+        std::process::exit(0);
+    }
+}
+
+/// A publisher attests to the custom network's subgraph for the whole run, so there is something to receive and
+/// compare against
+#[tokio::main]
+pub async fn run_custom_network() {
+    env::set_var(
+        "RADIO_NETWORKS",
+        format!("{MOCK_CUSTOM_NETWORK}:{MOCK_CUSTOM_NETWORK_INTERVAL}"),
+    );
+
+    let custom_network = NetworkName::from_string(MOCK_CUSTOM_NETWORK);
+    assert_eq!(
+        custom_network,
+        NetworkName::Custom(MOCK_CUSTOM_NETWORK.to_string())
+    );
+    assert!(
+        NETWORKS
+            .iter()
+            .any(|n| n.name == custom_network && n.interval == MOCK_CUSTOM_NETWORK_INTERVAL),
+        "{} was not registered from RADIO_NETWORKS",
+        MOCK_CUSTOM_NETWORK
+    );
+
+    let publisher = MockSender::generate(PUBLISHER_STAKE.to_string());
+    let mut config = RadioRuntimeConfig::new(false, true);
+    config.subgraphs = Some(vec![
+        MOCK_SUBGRAPH_MAINNET.to_string(),
+        MOCK_SUBGRAPH_CUSTOM.to_string(),
+    ]);
+    config.deployments = Some(vec![
+        MockDeployment::new(MOCK_SUBGRAPH_MAINNET, "mainnet", 0),
+        MockDeployment::new(MOCK_SUBGRAPH_CUSTOM, MOCK_CUSTOM_NETWORK, 0),
+    ]);
+    config.senders = vec![publisher.clone()];
+
+    // The publisher inherits RADIO_NETWORKS, so it knows the custom network as well
+    let boot_node_address = own_boot_node_address();
+    std::thread::spawn(move || {
+        start_publishers(
+            vec![publisher],
+            PUBLISH_RATE,
+            vec![MOCK_SUBGRAPH_CUSTOM.to_string()],
+            Some(MOCK_CUSTOM_NETWORK.to_string()),
+            boot_node_address,
+        )
+    });
+    run_test_radio(&config, success_handler).await;
+}
@@ -0,0 +1,41 @@
+use crate::setup::test_radio::run_test_radio;
+use colored::Colorize;
+use partial_application::partial;
+use poi_radio_e2e_tests::{latency::LATENCIES, utils::RadioRuntimeConfig, MessagesArc};
+use tracing::{debug, info};
+
+/// Accepted messages needed before a p95 says anything
+const MIN_SAMPLES: usize = 20;
+
+fn handler(max_latency_ms: u64, _messages: MessagesArc) {
+    let latencies = LATENCIES.get().unwrap().lock().unwrap();
+    let percentiles = match latencies.percentiles() {
+        Some(percentiles) if percentiles.samples >= MIN_SAMPLES => percentiles,
+        _ => return,
+    };
+    debug!(
+        "Delivery latency per sender: {:?}",
+        latencies.sender_percentiles()
+    );
+
+    assert!(
+        percentiles.p95 <= max_latency_ms,
+        "p95 delivery latency of {}ms over {} messages is above the {}ms bound",
+        percentiles.p95,
+        percentiles.samples,
+        max_latency_ms
+    );
+
+    info!(
+        "Delivery latency over {} messages: p50 {}ms, p95 {}ms, p99 {}ms",
+        percentiles.samples, percentiles.p50, percentiles.p95, percentiles.p99
+    );
+    info!("{}", "delivery_latency test is sucessful ✅".green());
+    std::process::exit(0);
+}
+
+#[tokio::main]
+pub async fn run_delivery_latency(max_latency_ms: u64) {
+    let config = RadioRuntimeConfig::new(false, true);
+    run_test_radio(&config, partial!(handler => max_latency_ms, _)).await;
+}
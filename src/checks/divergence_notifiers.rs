@@ -0,0 +1,139 @@
+use std::{env, fs, path::PathBuf, sync::Mutex as SyncMutex};
+
+use crate::setup::constants::MOCK_SUBGRAPH_GOERLI;
+use colored::Colorize;
+use once_cell::sync::Lazy;
+use poi_radio_e2e_tests::{
+    notifiers::NotifierConfig,
+    utils::{MockDeployment, RadioRuntimeConfig, MOCK_DIVERGED_POI},
+    MessagesArc,
+};
+use serde_json::Value;
+use tracing::{debug, info};
+use wiremock::{
+    matchers::{method, path},
+    Mock, MockServer, Request, Respond, ResponseTemplate,
+};
+
+use crate::setup::test_radio::run_test_radio;
+
+const POI_DIVERGES_AT: u64 = 14;
+
+/// Notifications the local HTTP sink has received
+static RECEIVED: Lazy<SyncMutex<Vec<Value>>> = Lazy::new(|| SyncMutex::new(Vec::new()));
+
+struct NotificationSink;
+
+impl Respond for NotificationSink {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        match serde_json::from_slice(&request.body) {
+            Ok(notification) => {
+                RECEIVED.lock().unwrap().push(notification);
+                ResponseTemplate::new(200)
+            }
+            Err(_) => ResponseTemplate::new(400),
+        }
+    }
+}
+
+fn notification_file(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("{name}-{}.jsonl", std::process::id()))
+}
+
+fn read_notifications(path: &PathBuf) -> Vec<Value> {
+    fs::read_to_string(path)
+        .unwrap_or_default()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+/// Asserts the context a notification carries and returns whether it reports the divergence we set up
+fn is_reported_divergence(notification: &Value) -> bool {
+    let message = notification["message"].as_str().unwrap();
+    assert!(
+        !message.contains('\x1b'),
+        "Notification message has color codes in it"
+    );
+    assert!(notification["block"].is_u64());
+
+    if notification["severity"] != "critical" {
+        assert_eq!(notification["severity"], "non_critical");
+        return false;
+    }
+
+    let block = notification["block"].as_u64().unwrap();
+    assert!(
+        block >= POI_DIVERGES_AT,
+        "Critical notification for block {block} before the POIs diverged"
+    );
+
+    let subgraph = notification["subgraphs"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|subgraph| subgraph["identifier"] == MOCK_SUBGRAPH_GOERLI)
+        .expect("Critical notification without the diverged subgraph");
+    assert_eq!(subgraph["network"], "goerli");
+    assert_eq!(subgraph["local_npoi"], MOCK_DIVERGED_POI);
+    assert_ne!(subgraph["most_attested_npoi"], subgraph["local_npoi"]);
+    assert!(!subgraph["remote_attestations"]
+        .as_array()
+        .unwrap()
+        .is_empty());
+
+    notification["divergences"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|divergence| divergence["identifier"] == MOCK_SUBGRAPH_GOERLI)
+}
+
+fn success_handler(_messages: MessagesArc) {
+    let received = RECEIVED.lock().unwrap();
+    debug!("Notifications received: {:?}", received);
+
+    let reported = received
+        .iter()
+        .filter(|notification| is_reported_divergence(notification))
+        .collect::<Vec<_>>();
+    let reported = match reported.last() {
+        Some(reported) => *reported,
+        None => return,
+    };
+
+    // The other notifiers are told the same thing as the webhook
+    for name in ["radio-notifications-file", "radio-notifications-command"] {
+        assert!(
+            read_notifications(&notification_file(name)).contains(reported),
+            "{name} is missing the critical notification"
+        );
+    }
+
+    info!("{}", "divergence_notifiers test is sucessful ✅".green());
+    std::process::exit(0);
+}
+
+#[tokio::main]
+pub async fn run_divergence_notifiers() {
+    let sink = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/notify"))
+        .respond_with(NotificationSink)
+        .mount(&sink)
+        .await;
+
+    let mut config = RadioRuntimeConfig::new(false, false);
+    config.subgraphs = Some(vec![MOCK_SUBGRAPH_GOERLI.to_string()]);
+    config.deployments = Some(vec![MockDeployment::new(MOCK_SUBGRAPH_GOERLI, "goerli", 5)]);
+    config.poi_diverges_at = Some(POI_DIVERGES_AT);
+    config.notifiers = vec![
+        NotifierConfig::Webhook(format!("{}/notify", sink.uri())),
+        NotifierConfig::File(notification_file("radio-notifications-file")),
+        NotifierConfig::Command(format!(
+            "{{ cat; echo; }} >> {}",
+            notification_file("radio-notifications-command").display()
+        )),
+    ];
+    run_test_radio(&config, success_handler).await;
+}
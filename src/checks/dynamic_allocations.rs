@@ -0,0 +1,76 @@
+use std::collections::HashSet;
+
+use crate::setup::constants::{MOCK_SUBGRAPH_GOERLI, MOCK_SUBGRAPH_MAINNET};
+use colored::Colorize;
+use poi_radio_e2e_tests::{
+    latency::sent_at_millis,
+    utils::{MockAllocationChange, RadioRuntimeConfig},
+    MessagesArc, SENT_MESSAGES, TOPIC_UPDATES,
+};
+use tracing::{debug, info};
+
+use crate::setup::test_radio::run_test_radio;
+
+/// Far enough in for mainnet to have been attested a few times before its allocation closes
+const ALLOCATION_CHANGE_BLOCK: u64 = 5;
+
+fn success_handler(messages: MessagesArc) {
+    let update = match TOPIC_UPDATES.get().unwrap().lock().unwrap().first() {
+        Some(update) => update.clone(),
+        None => return,
+    };
+    debug!("Topic update: {:?}", update);
+
+    assert_eq!(update.opened, vec![MOCK_SUBGRAPH_GOERLI.to_string()]);
+    assert_eq!(update.closed, vec![MOCK_SUBGRAPH_MAINNET.to_string()]);
+    assert_eq!(
+        update.topics.iter().collect::<HashSet<_>>(),
+        HashSet::from([&MOCK_SUBGRAPH_GOERLI.to_string()]),
+        "Content identifiers don't follow the allocations"
+    );
+
+    let sent = SENT_MESSAGES.get().unwrap().lock().unwrap();
+    let sent_after = &sent[update.sent_before..];
+    assert!(
+        sent_after
+            .iter()
+            .all(|m| m.identifier != MOCK_SUBGRAPH_MAINNET),
+        "Kept attesting to {} after its allocation closed",
+        MOCK_SUBGRAPH_MAINNET
+    );
+
+    // Nonces only have second precision, so a message stamped in the second of the update may predate it
+    let messages = messages.lock().unwrap();
+    let received_after = messages
+        .iter()
+        .filter(|(_, m)| sent_at_millis(m.nonce) >= update.updated_at + 1000)
+        .collect::<Vec<_>>();
+    assert!(
+        received_after
+            .iter()
+            .all(|(_, m)| m.identifier != MOCK_SUBGRAPH_MAINNET),
+        "Received a message on topic {} after its allocation closed",
+        MOCK_SUBGRAPH_MAINNET
+    );
+
+    let picked_up = sent_after
+        .iter()
+        .any(|m| m.identifier == MOCK_SUBGRAPH_GOERLI)
+        && received_after
+            .iter()
+            .any(|(_, m)| m.identifier == MOCK_SUBGRAPH_GOERLI);
+    if picked_up {
+        info!("{}", "dynamic_allocations test is sucessful ✅".green());
+        std::process::exit(0);
+    }
+}
+
+#[tokio::main]
+pub async fn run_dynamic_allocations() {
+    let mut config = RadioRuntimeConfig::new(false, true);
+    config.subgraphs = Some(vec![MOCK_SUBGRAPH_MAINNET.to_string()]);
+    config.allocation_schedule = vec![MockAllocationChange::new(ALLOCATION_CHANGE_BLOCK)
+        .opening(MOCK_SUBGRAPH_GOERLI)
+        .closing(MOCK_SUBGRAPH_MAINNET)];
+    run_test_radio(&config, success_handler).await;
+}
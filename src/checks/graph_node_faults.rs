@@ -0,0 +1,43 @@
+use colored::Colorize;
+use poi_radio_e2e_tests::{
+    utils::{faulted_requests, FaultKind, MockFault, RadioRuntimeConfig},
+    MessagesArc, SENT_MESSAGES,
+};
+use std::time::Duration;
+use tracing::{debug, info};
+
+use crate::{graphql::client::circuit_trips, setup::test_radio::run_test_radio};
+
+/// Enough failed requests to open the circuit at least once, counting the retries of each request
+const OUTAGE_REQUESTS: u64 = 12;
+/// Responses slower than the client's request timeout
+const SLOW_REQUESTS: u64 = 3;
+
+fn success_handler(_messages: MessagesArc) {
+    let sent = SENT_MESSAGES.get().unwrap().lock().unwrap();
+    debug!(
+        "Faulted requests: {}, circuit trips: {}, sent messages: {}",
+        faulted_requests(),
+        circuit_trips(),
+        sent.len()
+    );
+
+    // Every fault has been served, the circuit opened during the outage and the radio went back to attesting
+    if faulted_requests() == OUTAGE_REQUESTS + SLOW_REQUESTS
+        && circuit_trips() > 0
+        && sent.len() >= 3
+    {
+        info!("{}", "graph_node_faults test is sucessful ✅".green());
+        std::process::exit(0);
+    }
+}
+
+#[tokio::main]
+pub async fn run_graph_node_faults() {
+    let mut config = RadioRuntimeConfig::new(false, true);
+    config.graph_node_faults = vec![
+        MockFault::new(FaultKind::Unavailable, OUTAGE_REQUESTS),
+        MockFault::new(FaultKind::Slow(Duration::from_secs(12)), SLOW_REQUESTS),
+    ];
+    run_test_radio(&config, success_handler).await;
+}
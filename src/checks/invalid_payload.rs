@@ -1,30 +1,36 @@
-use crate::checks::deduplicate_messages;
+use crate::setup::constants::DUMMY_MSG_VALUE;
 use colored::Colorize;
-use poi_radio_e2e_tests::{utils::RadioRuntimeConfig, MessagesArc};
+use poi_radio_e2e_tests::{
+    rejections::{RejectionReason, REJECTIONS},
+    utils::RadioRuntimeConfig,
+    DummyMsg, MessagesArc,
+};
+use prost::Message;
 use tracing::{debug, info};
 
 use crate::setup::test_radio::run_test_radio;
 
-use std::any::type_name;
-
-fn type_of<T>(_: T) -> &'static str {
-    type_name::<T>()
-}
-
 fn success_handler(messages: MessagesArc) {
     let messages = messages.lock().unwrap();
+    let rejections = REJECTIONS.get().unwrap().lock().unwrap();
+
+    // Look for a DummyMsg sent by the invalid_payload instance among the type confusion rejections
+    let dummy_sender = rejections
+        .rejections
+        .iter()
+        .filter(|r| r.reason == RejectionReason::TypeConfusion)
+        .find_map(|r| {
+            let payload = DummyMsg::decode(r.payload.as_deref()?).ok()?;
+            (payload.dummy_value == DUMMY_MSG_VALUE).then(|| r.sender.clone())?
+        });
 
-    // Maybe pass in dynamic count here too
-    if messages.len() >= 5 {
-        let deduped = deduplicate_messages(&messages);
-        debug!("deduped {:?}", deduped);
+    if let Some(sender) = dummy_sender {
+        debug!("DummyMsg payload received from {}", sender);
 
-        info!("5 or more valid messages received! Checking payloads");
+        info!("DummyMsg received and rejected! Checking accepted messages");
         assert!(
-            messages
-                .iter()
-                .all(|m| !type_of(&m.1.payload).contains("DummyMsg")),
-            "Message found with invalid payload",
+            messages.iter().all(|m| m.0 != sender),
+            "Message from the invalid_payload instance was accepted",
         );
         info!("{}", "invalid_payload test is sucessful ✅".green());
         std::process::exit(0);
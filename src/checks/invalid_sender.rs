@@ -1,18 +1,25 @@
 use colored::Colorize;
-use poi_radio_e2e_tests::{utils::RadioRuntimeConfig, MessagesArc};
-use tracing::info;
+use poi_radio_e2e_tests::{rejections::RejectionReason, utils::RadioRuntimeConfig, MessagesArc};
+use tracing::{debug, info};
 
-use crate::setup::test_radio::run_test_radio;
+use crate::{checks::rejected_for, setup::test_radio::run_test_radio};
 
 fn success_handler(messages: MessagesArc) {
     let messages = messages.lock().unwrap();
+    debug!("{:?}", messages);
 
-    if messages.len() == 0 {
-        info!("{}", "invalid_sender test is sucessful ✅".green());
-        std::process::exit(0);
-    } else {
-        info!("{}", "invalid_sender test failed".red());
-        std::process::exit(1);
+    match rejected_for(&messages, RejectionReason::StakeBelowMinimum) {
+        Ok(true) => {
+            info!("{}", "invalid_sender test is sucessful ✅".green());
+            std::process::exit(0);
+        }
+        Ok(false) => {
+            debug!("Not enough rejected messages observed yet, waiting...");
+        }
+        Err(err) => {
+            info!("{}", format!("invalid_sender test failed: {err}").red());
+            std::process::exit(1);
+        }
     }
 }
 
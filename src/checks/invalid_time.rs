@@ -1,21 +1,27 @@
 use std::env;
 
 use colored::Colorize;
-use poi_radio_e2e_tests::{utils::RadioRuntimeConfig, MessagesArc};
+use poi_radio_e2e_tests::{rejections::RejectionReason, utils::RadioRuntimeConfig, MessagesArc};
 use tracing::{debug, info};
 
-use crate::setup::test_radio::run_test_radio;
+use crate::{checks::rejected_for, setup::test_radio::run_test_radio};
 
 fn success_handler(messages: MessagesArc) {
     let messages = messages.lock().unwrap();
     debug!("{:?}", messages);
 
-    if messages.len() == 0 {
-        info!("{}", "invalid_time test is sucessful ✅".green());
-        std::process::exit(0);
-    } else {
-        info!("{}", "invalid_time test failed".red());
-        std::process::exit(1);
+    match rejected_for(&messages, RejectionReason::StaleNonce) {
+        Ok(true) => {
+            info!("{}", "invalid_time test is sucessful ✅".green());
+            std::process::exit(0);
+        }
+        Ok(false) => {
+            debug!("Not enough rejected messages observed yet, waiting...");
+        }
+        Err(err) => {
+            info!("{}", format!("invalid_time test failed: {err}").red());
+            std::process::exit(1);
+        }
     }
 }
 
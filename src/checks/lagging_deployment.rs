@@ -0,0 +1,48 @@
+use std::collections::HashSet;
+
+use crate::setup::constants::{MOCK_SUBGRAPH_GOERLI, MOCK_SUBGRAPH_MAINNET};
+use colored::Colorize;
+use poi_radio_e2e_tests::{
+    utils::{unindexed_poi_requests, MockDeployment, RadioRuntimeConfig},
+    MessagesArc, SENT_MESSAGES,
+};
+use tracing::{debug, info};
+
+use crate::setup::test_radio::run_test_radio;
+
+/// Further behind than the goerli interval, so the chainhead's interval block is never indexed yet
+const GOERLI_LAG: u64 = 3;
+
+fn success_handler(_messages: MessagesArc) {
+    // Every attestation starts with a POI request, the mock counts the ones for blocks that aren't indexed yet
+    assert_eq!(
+        unindexed_poi_requests(),
+        0,
+        "Asked graph node for a POI beyond the deployment's indexed head"
+    );
+
+    let sent = SENT_MESSAGES.get().unwrap().lock().unwrap();
+    debug!("Sent messages: {:?}", sent);
+
+    // Catching up means the lagging deployment keeps being attested as its indexed head moves
+    let lagging_blocks = sent
+        .iter()
+        .filter(|m| m.identifier == MOCK_SUBGRAPH_GOERLI)
+        .map(|m| m.block_number)
+        .collect::<HashSet<u64>>();
+
+    if lagging_blocks.len() >= 3 {
+        info!("{}", "lagging_deployment test is sucessful ✅".green());
+        std::process::exit(0);
+    }
+}
+
+#[tokio::main]
+pub async fn run_lagging_deployment() {
+    let mut config = RadioRuntimeConfig::new(false, true);
+    config.deployments = Some(vec![
+        MockDeployment::new(MOCK_SUBGRAPH_MAINNET, "mainnet", 0),
+        MockDeployment::new(MOCK_SUBGRAPH_GOERLI, "goerli", 5).lagging(GOERLI_LAG),
+    ]);
+    run_test_radio(&config, success_handler).await;
+}
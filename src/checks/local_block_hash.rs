@@ -0,0 +1,104 @@
+use colored::Colorize;
+use graphcast_sdk::graphcast_agent::message_typing::GraphcastMessage;
+use poi_radio_e2e_tests::{
+    rejections::{RejectionLog, RejectionReason, REJECTIONS},
+    utils::{
+        generate_random_address, setup_mock_server, MockDeployment, RadioRuntimeConfig,
+        MOCK_BLOCK_HASH,
+    },
+    NetworkName, RadioPayloadMessage,
+};
+use std::sync::{Arc, Mutex as SyncMutex};
+use tracing::info;
+
+use crate::{
+    graphql::BlockHashResolver,
+    setup::{
+        constants::{MOCK_SUBGRAPH_GOERLI, MOCK_SUBGRAPH_MAINNET},
+        test_radio::reject_unverified_block_hashes,
+    },
+};
+
+const BLOCK_NUMBER: u64 = 8;
+const FORKED_BLOCK_HASH: &str = "7c2e4f1aa0d35b9e61f8c4d2b7a09e3f58d1c6b4e2a7f90d3b8c5e1f6a4d2b07";
+
+fn received_message(
+    identifier: &str,
+    network: &str,
+    block_hash: &str,
+) -> (String, GraphcastMessage<RadioPayloadMessage>) {
+    let config = RadioRuntimeConfig::default_config();
+
+    (
+        generate_random_address(),
+        GraphcastMessage {
+            identifier: identifier.to_string(),
+            payload: Some(RadioPayloadMessage::new(identifier.to_string(), config.poi)),
+            nonce: 0,
+            network: network.to_string(),
+            block_number: BLOCK_NUMBER,
+            block_hash: block_hash.to_string(),
+            signature: String::new(),
+        },
+    )
+}
+
+#[tokio::main]
+pub async fn run_local_block_hash() {
+    let config = RadioRuntimeConfig::default_config();
+    let subgraphs = vec![
+        MOCK_SUBGRAPH_MAINNET.to_string(),
+        MOCK_SUBGRAPH_GOERLI.to_string(),
+    ];
+    let mock_server_uri = setup_mock_server(
+        BLOCK_NUMBER,
+        0,
+        &generate_random_address(),
+        &generate_random_address(),
+        &subgraphs,
+        &MockDeployment::defaults(&subgraphs),
+        &config,
+    )
+    .await;
+    _ = REJECTIONS.set(Arc::new(SyncMutex::new(RejectionLog::default())));
+
+    // Hashes are compared regardless of case and 0x prefix, a different hash for the same block is a fork
+    let matching = received_message(
+        MOCK_SUBGRAPH_MAINNET,
+        "mainnet",
+        &format!("0x{}", MOCK_BLOCK_HASH.to_uppercase()),
+    );
+    let forked = received_message(MOCK_SUBGRAPH_GOERLI, "goerli", FORKED_BLOCK_HASH);
+    let messages = Arc::new(SyncMutex::new(vec![matching.clone(), forked.clone()]));
+
+    let mut resolver = BlockHashResolver::new(format!("{}{}", mock_server_uri, "/graphql"));
+    reject_unverified_block_hashes(&messages, &mut resolver).await;
+
+    let remaining = messages.lock().unwrap().clone();
+    assert_eq!(
+        remaining.len(),
+        1,
+        "Expected only the matching message to remain"
+    );
+    assert_eq!(remaining[0].0, matching.0);
+
+    let log = REJECTIONS.get().unwrap().lock().unwrap();
+    assert_eq!(log.count(RejectionReason::LocalBlockHashMismatch), 1);
+    assert_eq!(
+        log.rejections[0].sender.as_deref(),
+        Some(forked.0.as_str()),
+        "Rejection was not attributed to the sender of the forked message"
+    );
+    info!("Forked block hash rejected");
+
+    for network in [NetworkName::Mainnet, NetworkName::Goerli] {
+        assert_eq!(
+            resolver.cached(&network, BLOCK_NUMBER).map(String::as_str),
+            Some(MOCK_BLOCK_HASH),
+            "Block hash on {network} was not cached"
+        );
+    }
+    info!("Resolved block hashes cached");
+
+    info!("{}", "local_block_hash test is sucessful ✅".green());
+}
@@ -0,0 +1,96 @@
+use colored::Colorize;
+use num_bigint::BigUint;
+use poi_radio_e2e_tests::{
+    utils::{MockSender, RadioRuntimeConfig},
+    MessagesArc, LAST_ROUND, PAYLOAD_VERSIONS,
+};
+use std::collections::{BTreeSet, HashSet};
+use tracing::{debug, info};
+
+use crate::setup::test_radio::run_test_radio;
+
+/// Stake the mock network subgraph reports for the sender's Indexer
+fn stake_of(senders: &[MockSender], indexer_stake: &str, sender: &str) -> BigUint {
+    senders
+        .iter()
+        .find(|mock| mock.graphcast_id().eq_ignore_ascii_case(sender))
+        .map_or(indexer_stake, |mock| &mock.stake)
+        .parse()
+        .unwrap()
+}
+
+/// Looks at the attestations `process_messages` aggregated from the stored messages in the last round. v1 and v2
+/// senders attesting to the same NPOI have to end up in a single attestation that carries the stake of all of them
+fn success_handler(senders: &[MockSender], indexer_stake: &str, _messages: MessagesArc) {
+    let round = LAST_ROUND.get().unwrap().lock().unwrap().clone();
+    let versions = PAYLOAD_VERSIONS.get().unwrap().lock().unwrap().clone();
+    let versions_of = |sender: &String| {
+        versions
+            .get(sender)
+            .cloned()
+            .unwrap_or_else(|| BTreeSet::from([1]))
+    };
+
+    let mut mixed = false;
+    for (identifier, blocks) in round.remote.iter() {
+        for (block, attestations) in blocks.iter() {
+            // Every instance reports the same mock POI, so attestations may only ever split along version lines by
+            // mistake
+            assert!(
+                attestations.len() == 1,
+                "Attestations for subgraph {identifier} on block {block} split into {} NPOIs: {:?}",
+                attestations.len(),
+                attestations
+            );
+
+            let attestation = &attestations[0];
+            let distinct = attestation.senders.iter().collect::<HashSet<_>>();
+            assert_eq!(
+                distinct.len(),
+                attestation.senders.len(),
+                "A sender was counted more than once for subgraph {identifier} on block {block}"
+            );
+            let sender_versions = attestation
+                .senders
+                .iter()
+                .flat_map(versions_of)
+                .collect::<BTreeSet<u32>>();
+            debug!(
+                "Attestation for subgraph {identifier} on block {block} from {:?} with versions {:?}",
+                attestation.senders, sender_versions
+            );
+            if sender_versions.len() < 2 {
+                continue;
+            }
+
+            let stake = attestation
+                .senders
+                .iter()
+                .map(|sender| stake_of(senders, indexer_stake, sender))
+                .sum::<BigUint>();
+            assert_eq!(
+                attestation.stake_weight, stake,
+                "Attestation for subgraph {identifier} on block {block} doesn't carry the stake of all its senders"
+            );
+            mixed = true;
+        }
+    }
+
+    if mixed {
+        info!("v1 and v2 attestations aggregated into the same NPOI!");
+        info!("{}", "mixed_versions test is sucessful ✅".green());
+        std::process::exit(0);
+    }
+}
+
+/// Needs both v1 and v2 senders, which `orchestrate` can't start, run it through the mixed_versions scenario
+#[tokio::main]
+pub async fn run_mixed_versions() {
+    let config = RadioRuntimeConfig::new(false, true);
+    let senders = config.senders.clone();
+    let indexer_stake = config.indexer_stake.clone();
+    run_test_radio(&config, move |messages| {
+        success_handler(&senders, &indexer_stake, messages)
+    })
+    .await;
+}
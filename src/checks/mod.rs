@@ -1,15 +1,34 @@
 use std::collections::HashSet;
 
 use graphcast_sdk::graphcast_agent::message_typing::GraphcastMessage;
-use poi_radio_e2e_tests::RadioPayloadMessage;
+use poi_radio_e2e_tests::{
+    rejections::{RejectionLog, RejectionReason, REJECTIONS},
+    RadioPayloadMessage,
+};
 
+pub mod attestation_report;
+pub mod batched_poi;
+pub mod block_alignment;
 pub mod correct_filtering_default_topics;
 pub mod correct_filtering_different_topics;
+pub mod custom_network;
+pub mod delivery_latency;
+pub mod divergence_notifiers;
+pub mod dynamic_allocations;
+pub mod graph_node_faults;
 pub mod invalid_block_hash;
 pub mod invalid_payload;
 pub mod invalid_sender;
 pub mod invalid_time;
+pub mod lagging_deployment;
+pub mod local_block_hash;
+pub mod mixed_versions;
+pub mod multi_chain;
+pub mod operator_rotation;
+pub mod poi_divergence;
 pub mod skip_messages_from_self;
+pub mod skip_unhealthy;
+pub mod stake_threshold;
 pub mod test_num_messages;
 pub mod test_poi_ok;
 
@@ -23,3 +42,140 @@ pub fn deduplicate_messages(
         .cloned()
         .collect()
 }
+
+/// Minimum number of messages a negative check needs to see rejected before it can pass
+pub const MIN_REJECTED_MESSAGES: usize = 5;
+
+/// Looks for positive evidence that messages were delivered to this instance and then dropped for the expected reason.
+/// Returns `Ok(false)` while not enough messages have been observed yet, and `Err` as soon as a message was accepted
+/// or rejected for any other reason. Messages this instance received from itself are not counted either way.
+pub fn rejected_for(
+    messages: &[(String, GraphcastMessage<RadioPayloadMessage>)],
+    expected: RejectionReason,
+) -> Result<bool, String> {
+    rejected_in(
+        &REJECTIONS.get().unwrap().lock().unwrap(),
+        messages,
+        expected,
+    )
+}
+
+/// `rejected_for` against the given log
+fn rejected_in(
+    log: &RejectionLog,
+    messages: &[(String, GraphcastMessage<RadioPayloadMessage>)],
+    expected: RejectionReason,
+) -> Result<bool, String> {
+    if !messages.is_empty() {
+        return Err(format!("{} message(s) were accepted", messages.len()));
+    }
+
+    let rejections = log
+        .rejections
+        .iter()
+        .filter(|rejection| rejection.reason != RejectionReason::SelfMessage)
+        .collect::<Vec<_>>();
+
+    if let Some(unexpected) = rejections.iter().find(|r| r.reason != expected) {
+        return Err(format!(
+            "Message rejected for {} instead of {}: {}",
+            unexpected.reason, expected, unexpected.error
+        ));
+    }
+
+    let observed = (log.observed as usize).saturating_sub(log.count(RejectionReason::SelfMessage));
+    if observed < MIN_REJECTED_MESSAGES {
+        return Ok(false);
+    }
+
+    Ok(rejections.len() == observed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use poi_radio_e2e_tests::rejections::Rejection;
+
+    fn log_with(observed: u64, reasons: &[RejectionReason]) -> RejectionLog {
+        let mut log = RejectionLog {
+            observed,
+            ..Default::default()
+        };
+        for reason in reasons {
+            log.reject(Rejection::new(None, *reason, reason.to_string()));
+        }
+        log
+    }
+
+    fn accepted() -> (String, GraphcastMessage<RadioPayloadMessage>) {
+        (
+            "0xsender".to_string(),
+            GraphcastMessage {
+                identifier: "QmSubgraph".to_string(),
+                payload: Some(RadioPayloadMessage::new(
+                    "QmSubgraph".to_string(),
+                    "0xpoi".to_string(),
+                )),
+                nonce: 0,
+                network: "mainnet".to_string(),
+                block_number: 0,
+                block_hash: String::new(),
+                signature: String::new(),
+            },
+        )
+    }
+
+    #[test]
+    fn waits_for_enough_rejections() {
+        let reasons = [RejectionReason::StaleNonce; MIN_REJECTED_MESSAGES - 1];
+        let log = log_with(reasons.len() as u64, &reasons);
+        assert_eq!(
+            rejected_in(&log, &[], RejectionReason::StaleNonce),
+            Ok(false)
+        );
+    }
+
+    #[test]
+    fn passes_once_every_message_was_rejected_for_the_reason() {
+        let reasons = [RejectionReason::StaleNonce; MIN_REJECTED_MESSAGES];
+        let log = log_with(reasons.len() as u64, &reasons);
+        assert_eq!(
+            rejected_in(&log, &[], RejectionReason::StaleNonce),
+            Ok(true)
+        );
+
+        // A message that was observed but neither accepted nor rejected keeps it from passing
+        let log = log_with(reasons.len() as u64 + 1, &reasons);
+        assert_eq!(
+            rejected_in(&log, &[], RejectionReason::StaleNonce),
+            Ok(false)
+        );
+    }
+
+    #[test]
+    fn fails_on_accepted_messages_and_other_reasons() {
+        let log = log_with(0, &[]);
+        assert!(rejected_in(&log, &[accepted()], RejectionReason::StaleNonce).is_err());
+
+        let log = log_with(2, &[RejectionReason::StaleNonce, RejectionReason::Other]);
+        assert!(rejected_in(&log, &[], RejectionReason::StaleNonce).is_err());
+    }
+
+    #[test]
+    fn leaves_self_messages_out() {
+        let mut reasons = vec![RejectionReason::SelfMessage; 3];
+        reasons.extend([RejectionReason::StaleNonce; MIN_REJECTED_MESSAGES]);
+        let log = log_with(reasons.len() as u64, &reasons);
+        assert_eq!(
+            rejected_in(&log, &[], RejectionReason::StaleNonce),
+            Ok(true)
+        );
+
+        // Self messages recorded without being observed don't underflow the count
+        let log = log_with(0, &[RejectionReason::SelfMessage; 3]);
+        assert_eq!(
+            rejected_in(&log, &[], RejectionReason::StaleNonce),
+            Ok(false)
+        );
+    }
+}
@@ -0,0 +1,58 @@
+use std::collections::HashSet;
+
+use crate::setup::constants::{MOCK_SUBGRAPH_GOERLI, MOCK_SUBGRAPH_MAINNET};
+use colored::Colorize;
+use poi_radio_e2e_tests::{
+    utils::{MockDeployment, RadioRuntimeConfig},
+    MessagesArc, NetworkName, NETWORKS, SENT_MESSAGES,
+};
+use tracing::{debug, info};
+
+use crate::setup::test_radio::run_test_radio;
+
+fn success_handler(_messages: MessagesArc) {
+    let sent = SENT_MESSAGES.get().unwrap().lock().unwrap();
+    debug!("Sent messages: {:?}", sent);
+
+    let goerli = NetworkName::from_string("goerli");
+    let interval = NETWORKS
+        .iter()
+        .find(|n| n.name == goerli)
+        .map(|n| n.interval)
+        .unwrap();
+
+    // The multi-chain deployment is attested on its first chain only, at that chain's interval
+    let multi_chain_blocks = sent
+        .iter()
+        .filter(|m| m.identifier == MOCK_SUBGRAPH_GOERLI)
+        .map(|m| {
+            assert_eq!(
+                m.network, goerli,
+                "Multi-chain deployment attested on {} instead of its first chain",
+                m.network
+            );
+            assert_eq!(
+                m.block_number % interval,
+                0,
+                "Block {} is not aligned to the goerli interval",
+                m.block_number
+            );
+            m.block_number
+        })
+        .collect::<HashSet<u64>>();
+
+    if multi_chain_blocks.len() >= 3 && sent.iter().any(|m| m.identifier == MOCK_SUBGRAPH_MAINNET) {
+        info!("{}", "multi_chain test is sucessful ✅".green());
+        std::process::exit(0);
+    }
+}
+
+#[tokio::main]
+pub async fn run_multi_chain() {
+    let mut config = RadioRuntimeConfig::new(false, true);
+    config.deployments = Some(vec![
+        MockDeployment::new(MOCK_SUBGRAPH_MAINNET, "mainnet", 0),
+        MockDeployment::new(MOCK_SUBGRAPH_GOERLI, "goerli", 5).with_chain("gnosis", 7),
+    ]);
+    run_test_radio(&config, success_handler).await;
+}
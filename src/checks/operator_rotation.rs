@@ -0,0 +1,124 @@
+use colored::Colorize;
+use num_bigint::BigUint;
+use once_cell::sync::OnceCell;
+use poi_radio_e2e_tests::{
+    rejections::{RejectionReason, REJECTIONS},
+    utils::{MockOperatorRotation, MockSender, RadioRuntimeConfig},
+    MessagesArc, LAST_ROUND,
+};
+use tracing::{debug, info};
+
+use crate::setup::{
+    load::{own_boot_node_address, start_publishers},
+    test_radio::run_test_radio,
+};
+
+/// Mock block number at which the registry moves the Indexer over to its new operator key
+const ROTATION_BLOCK: u64 = 4;
+/// Only the rotating Indexer attests to this deployment, so its stake is all there is to count on it
+const ROTATION_TOPIC: &str = "QmRotatingIndexer00000000000000000000000000000";
+const ROTATING_STAKE: &str = "200000000000000000000000";
+/// Attestations each operator key broadcasts per second
+const PUBLISH_RATE: f64 = 0.5;
+
+/// Round of comparing in which the handler first turned the old key away. Rounds up to it may have aggregated
+/// before the registry moved the Indexer over, only the ones after it are sure not to have
+static SWITCHED_AT: OnceCell<u64> = OnceCell::new();
+
+/// Whether the handler has been handed a message from the sender that failed validation for the sender not
+/// being registered
+fn unregistered(sender: &MockSender) -> bool {
+    REJECTIONS
+        .get()
+        .unwrap()
+        .lock()
+        .unwrap()
+        .per_sender
+        .iter()
+        .any(|(address, reasons)| {
+            address.eq_ignore_ascii_case(&sender.graphcast_id())
+                && reasons.contains_key(&RejectionReason::UnregisteredSender)
+        })
+}
+
+/// Whichever key the registry resolves at the time carries the Indexer's stake, and never both of them
+fn success_handler(rotation: &MockOperatorRotation, _messages: MessagesArc) {
+    let round = LAST_ROUND.get().unwrap().lock().unwrap().clone();
+    let stake = ROTATING_STAKE.parse::<BigUint>().unwrap();
+    let blocks = round
+        .remote
+        .get(ROTATION_TOPIC)
+        .cloned()
+        .unwrap_or_default();
+    debug!("Attestations of the rotating Indexer: {:?}", blocks);
+
+    for (block, attestations) in &blocks {
+        let counted = attestations
+            .iter()
+            .map(|attestation| attestation.stake_weight.clone())
+            .sum::<BigUint>();
+        assert!(
+            counted <= stake,
+            "Counted {counted} stake at block {block} for an Indexer with {stake}"
+        );
+    }
+
+    if !unregistered(&rotation.from) {
+        debug!("The old operator key hasn't been turned away yet");
+        return;
+    }
+    let switched_at = *SWITCHED_AT.get_or_init(|| round.round);
+    if round.round <= switched_at {
+        return;
+    }
+
+    let old_key = rotation.from.graphcast_id();
+    let new_key = rotation.to.graphcast_id();
+    assert!(
+        blocks.values().flatten().all(|attestation| !attestation
+            .senders
+            .iter()
+            .any(|sender| sender.eq_ignore_ascii_case(&old_key))),
+        "Stake is still attributed to the retired key {old_key}"
+    );
+
+    let attributed = blocks.values().flatten().any(|attestation| {
+        attestation.stake_weight == stake
+            && attestation.senders.len() == 1
+            && attestation.senders[0].eq_ignore_ascii_case(&new_key)
+    });
+    if attributed {
+        info!("{}", "operator_rotation test is sucessful ✅".green());
+        std::process::exit(0);
+    }
+}
+
+/// Both operator keys broadcast attestations through publishers of their own for the whole run, as an operator that
+/// hasn't stopped its old radio would
+#[tokio::main]
+pub async fn run_operator_rotation() {
+    let operator = MockSender::generate(ROTATING_STAKE.to_string());
+    let rotation = MockOperatorRotation::new(ROTATION_BLOCK, &operator);
+
+    let mut config = RadioRuntimeConfig::new(false, true);
+    config.observer = true;
+    config.subgraphs = Some(vec![ROTATION_TOPIC.to_string()]);
+    config.senders = vec![operator];
+    config.operator_rotations = vec![rotation.clone()];
+
+    let keys = vec![rotation.from.clone(), rotation.to.clone()];
+    let boot_node_address = own_boot_node_address();
+    std::thread::spawn(move || {
+        start_publishers(
+            keys,
+            PUBLISH_RATE,
+            vec![ROTATION_TOPIC.to_string()],
+            None,
+            boot_node_address,
+        )
+    });
+    run_test_radio(&config, move |messages| {
+        success_handler(&rotation, messages)
+    })
+    .await;
+}
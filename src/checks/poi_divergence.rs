@@ -0,0 +1,50 @@
+use crate::setup::constants::MOCK_SUBGRAPH_GOERLI;
+use colored::Colorize;
+use poi_radio_e2e_tests::{
+    utils::{MockDeployment, RadioRuntimeConfig},
+    MessagesArc, DIVERGENCES,
+};
+use tracing::{debug, info};
+
+use crate::setup::test_radio::run_test_radio;
+
+/// Our mock graph node's POIs depart from the ones other instances attest to from this block on
+const POI_DIVERGES_AT: u64 = 14;
+
+fn success_handler(_messages: MessagesArc) {
+    let divergences = DIVERGENCES.get().unwrap().lock().unwrap();
+    debug!("Divergences: {:?}", divergences);
+
+    for divergence in divergences.iter() {
+        assert_eq!(divergence.identifier, MOCK_SUBGRAPH_GOERLI);
+        assert!(
+            divergence.first_diverged_block >= POI_DIVERGES_AT,
+            "Bisected to block {} before the POIs diverged",
+            divergence.first_diverged_block
+        );
+        if let Some(block) = divergence.last_matching_block {
+            assert!(
+                block < POI_DIVERGES_AT,
+                "Block {block} reported as matching after the POIs diverged"
+            );
+        }
+    }
+
+    // Only a divergence with a matching block before it has been narrowed down
+    if divergences
+        .iter()
+        .any(|divergence| divergence.last_matching_block.is_some())
+    {
+        info!("{}", "poi_divergence test is sucessful ✅".green());
+        std::process::exit(0);
+    }
+}
+
+#[tokio::main]
+pub async fn run_poi_divergence() {
+    let mut config = RadioRuntimeConfig::new(false, false);
+    config.subgraphs = Some(vec![MOCK_SUBGRAPH_GOERLI.to_string()]);
+    config.deployments = Some(vec![MockDeployment::new(MOCK_SUBGRAPH_GOERLI, "goerli", 5)]);
+    config.poi_diverges_at = Some(POI_DIVERGES_AT);
+    run_test_radio(&config, success_handler).await;
+}
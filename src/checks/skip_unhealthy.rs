@@ -0,0 +1,42 @@
+use crate::setup::constants::{MOCK_SUBGRAPH_GOERLI, MOCK_SUBGRAPH_MAINNET};
+use colored::Colorize;
+use poi_radio_e2e_tests::{
+    utils::{MockDeployment, RadioRuntimeConfig},
+    MessagesArc, SENT_MESSAGES,
+};
+use tracing::{debug, info};
+
+use crate::setup::test_radio::run_test_radio;
+
+fn success_handler(_messages: MessagesArc) {
+    let sent = SENT_MESSAGES.get().unwrap().lock().unwrap();
+    debug!("Sent messages: {:?}", sent);
+
+    assert!(
+        sent.iter().all(|m| m.identifier != MOCK_SUBGRAPH_GOERLI),
+        "Message sent for failed deployment {}",
+        MOCK_SUBGRAPH_GOERLI
+    );
+
+    // Attesting keeps going for the healthy deployment
+    if sent
+        .iter()
+        .filter(|m| m.identifier == MOCK_SUBGRAPH_MAINNET)
+        .count()
+        >= 3
+    {
+        info!("{}", "skip_unhealthy test is sucessful ✅".green());
+        std::process::exit(0);
+    }
+}
+
+#[tokio::main]
+pub async fn run_skip_unhealthy() {
+    let mut config = RadioRuntimeConfig::new(false, true);
+    config.deployments = Some(vec![
+        MockDeployment::new(MOCK_SUBGRAPH_MAINNET, "mainnet", 0),
+        MockDeployment::new(MOCK_SUBGRAPH_GOERLI, "goerli", 5)
+            .failed("Mapping aborted at ./src/mapping.ts, line 12, column 4"),
+    ]);
+    run_test_radio(&config, success_handler).await;
+}
@@ -0,0 +1,165 @@
+use chrono::Utc;
+use colored::Colorize;
+use num_bigint::BigUint;
+use once_cell::sync::OnceCell;
+use poi_radio_e2e_tests::{
+    latency::sent_at_millis,
+    rejections::{RejectionReason, REJECTIONS},
+    utils::{RadioRuntimeConfig, MOCK_MINIMUM_INDEXER_STAKE},
+    MessagesArc,
+};
+use tracing::{debug, info};
+
+use crate::{
+    checks::{rejected_for, MIN_REJECTED_MESSAGES},
+    setup::test_radio::run_test_radio,
+};
+
+/// Minimum number of messages a positive check needs to have accepted before it can pass
+const MIN_ACCEPTED_MESSAGES: usize = 5;
+/// Rounds in, far enough for the other Indexers to have been accepted a few times before the minimum goes up
+const THRESHOLD_CHANGE_ROUND: u64 = 6;
+
+/// Where the stake of the other Indexers sits relative to `minimumIndexerStake`
+#[derive(Clone, Copy, Debug)]
+pub enum StakeBoundary {
+    Below,
+    At,
+    Above,
+}
+
+impl StakeBoundary {
+    fn stake(&self, minimum: &BigUint) -> BigUint {
+        match self {
+            StakeBoundary::Below => minimum - 1u32,
+            StakeBoundary::At => minimum.clone(),
+            StakeBoundary::Above => minimum + 1u32,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            StakeBoundary::Below => "stake_below_minimum",
+            StakeBoundary::At => "stake_at_minimum",
+            StakeBoundary::Above => "stake_above_minimum",
+        }
+    }
+}
+
+fn minimum_stake() -> BigUint {
+    MOCK_MINIMUM_INDEXER_STAKE.parse().unwrap()
+}
+
+fn stake_rejections() -> usize {
+    REJECTIONS
+        .get()
+        .unwrap()
+        .lock()
+        .unwrap()
+        .count(RejectionReason::StakeBelowMinimum)
+}
+
+fn boundary_handler(boundary: StakeBoundary, messages: MessagesArc) {
+    let messages = messages.lock().unwrap();
+    debug!("{:?}", messages);
+
+    let verdict = match boundary {
+        StakeBoundary::Below => rejected_for(&messages, RejectionReason::StakeBelowMinimum),
+        StakeBoundary::At | StakeBoundary::Above => match stake_rejections() {
+            0 => Ok(messages.len() >= MIN_ACCEPTED_MESSAGES),
+            rejected => Err(format!(
+                "{rejected} message(s) from Indexers with enough stake were rejected"
+            )),
+        },
+    };
+
+    match verdict {
+        Ok(true) => {
+            info!(
+                "{}",
+                format!("{} test is sucessful ✅", boundary.name()).green()
+            );
+            std::process::exit(0);
+        }
+        Ok(false) => {
+            debug!("Not enough messages observed yet, waiting...");
+        }
+        Err(err) => {
+            info!(
+                "{}",
+                format!("{} test failed: {err}", boundary.name()).red()
+            );
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Every other Indexer stakes one wei below, exactly or one wei above the minimum
+#[tokio::main]
+async fn run_stake_boundary(boundary: StakeBoundary) {
+    let mut config = RadioRuntimeConfig::new(false, true);
+    config.indexer_stake = boundary.stake(&minimum_stake()).to_string();
+    run_test_radio(&config, move |messages| {
+        boundary_handler(boundary, messages)
+    })
+    .await;
+}
+
+pub fn run_stake_below_minimum() {
+    run_stake_boundary(StakeBoundary::Below);
+}
+
+pub fn run_stake_at_minimum() {
+    run_stake_boundary(StakeBoundary::At);
+}
+
+pub fn run_stake_above_minimum() {
+    run_stake_boundary(StakeBoundary::Above);
+}
+
+/// How many messages had been accepted when the first stake rejection showed up, and when that was
+static RAISED: OnceCell<(usize, i64)> = OnceCell::new();
+
+fn threshold_change_handler(messages: MessagesArc) {
+    let messages = messages.lock().unwrap();
+    let rejected = stake_rejections();
+    if rejected == 0 {
+        debug!(
+            "Minimum stake not raised yet, {} messages accepted",
+            messages.len()
+        );
+        return;
+    }
+
+    let (accepted, raised_at) =
+        *RAISED.get_or_init(|| (messages.len(), Utc::now().timestamp_millis()));
+    assert!(
+        accepted > 0,
+        "No messages were accepted while the Indexers staked exactly the minimum"
+    );
+
+    // Nonces only have second precision, a message stamped in the second the rejections showed up may predate them
+    let late = messages
+        .iter()
+        .filter(|(_, m)| sent_at_millis(m.nonce) >= raised_at + 1000)
+        .count();
+    assert_eq!(
+        late, 0,
+        "Accepted messages sent after the minimum stake was raised past the Indexers' stake"
+    );
+
+    if rejected >= MIN_REJECTED_MESSAGES {
+        info!("{}", "stake_threshold_change test is sucessful ✅".green());
+        std::process::exit(0);
+    }
+}
+
+/// The other Indexers stake exactly the minimum until it is raised by one wei mid-run
+#[tokio::main]
+pub async fn run_stake_threshold_change() {
+    let minimum = minimum_stake();
+    let mut config = RadioRuntimeConfig::new(false, true);
+    config.indexer_stake = minimum.to_string();
+    config.minimum_stake_changes = vec![(THRESHOLD_CHANGE_ROUND, (minimum + 1u32).to_string())];
+    run_test_radio(&config, threshold_change_handler).await;
+}
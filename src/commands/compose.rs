@@ -0,0 +1,270 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::Path,
+    process::Command,
+    str::FromStr,
+};
+
+use anyhow::{anyhow, bail};
+use hex::encode;
+use serde::Deserialize;
+use tracing::info;
+
+use super::orchestrate::{peer_id, random_key};
+use crate::{Check, Instance};
+
+/// Every node listens on the same port, each in its own container
+const WAKU_PORT: u16 = 60000;
+const BOOT_NODE: &str = "boot-node";
+
+/// Named like the services of the hand written docker-compose.yml
+fn check_service(check: &str) -> String {
+    format!("test-check-{}", check.replace('_', "-"))
+}
+
+fn default_env_file() -> String {
+    "./.env".to_string()
+}
+
+fn default_count() -> u32 {
+    1
+}
+
+/// A group of identical setup instances
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioInstance {
+    pub instance: String,
+    #[serde(default = "default_count")]
+    pub count: u32,
+    /// Extra command line arguments, such as `--payload-version=2`
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Set on top of the env file
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioCheck {
+    pub check: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+}
+
+/// A multi-node topology to reproduce locally, read from a JSON file
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    #[serde(default = "default_env_file")]
+    pub env_file: String,
+    #[serde(default)]
+    pub instances: Vec<ScenarioInstance>,
+    pub check: ScenarioCheck,
+}
+
+impl Scenario {
+    pub fn read(path: &Path) -> Result<Self, anyhow::Error> {
+        let scenario: Scenario = serde_json::from_str(&fs::read_to_string(path)?)
+            .map_err(|e| anyhow!("Invalid scenario {}: {e}", path.display()))?;
+
+        for group in &scenario.instances {
+            Instance::from_str(&group.instance).map_err(|e| anyhow!(e))?;
+        }
+        Check::from_str(&scenario.check.check).map_err(|e| anyhow!(e))?;
+
+        Ok(scenario)
+    }
+}
+
+struct Service {
+    name: String,
+    command: Vec<String>,
+    environment: BTreeMap<String, String>,
+    depends_on: Vec<String>,
+    /// Whether the node reports healthy once its Waku port is open, the check only ever runs to its verdict
+    healthcheck: bool,
+}
+
+impl Service {
+    fn new(name: String, flag: String, args: &[String], env: &BTreeMap<String, String>) -> Self {
+        let mut environment = BTreeMap::from([
+            (
+                "PRIVATE_KEY".to_string(),
+                encode(random_key().secret_bytes()),
+            ),
+            ("WAKU_PORT".to_string(), WAKU_PORT.to_string()),
+        ]);
+        environment.extend(env.clone());
+
+        Service {
+            name,
+            command: ["cargo", "run", "--"]
+                .iter()
+                .map(|arg| arg.to_string())
+                .chain(std::iter::once(flag))
+                .chain(args.iter().cloned())
+                .collect(),
+            environment,
+            depends_on: vec![],
+            healthcheck: true,
+        }
+    }
+
+    fn to_yaml(&self, env_file: &str) -> String {
+        // JSON strings are valid double quoted YAML scalars
+        let quote = |value: &str| serde_json::to_string(value).unwrap();
+        let list = |values: &[String]| {
+            values
+                .iter()
+                .map(|value| quote(value))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        let mut yaml = format!(
+            "  {}:\n    build: .\n    env_file:\n      - {}\n",
+            self.name,
+            quote(env_file)
+        );
+        yaml.push_str("    environment:\n");
+        for (key, value) in &self.environment {
+            yaml.push_str(&format!("      {key}: {}\n", quote(value)));
+        }
+        yaml.push_str(&format!("    command: [ {} ]\n", list(&self.command)));
+        if !self.depends_on.is_empty() {
+            yaml.push_str("    depends_on:\n");
+            for dependency in &self.depends_on {
+                yaml.push_str(&format!(
+                    "      {dependency}:\n        condition: service_healthy\n"
+                ));
+            }
+        }
+        if self.healthcheck {
+            let test = [
+                "CMD-SHELL".to_string(),
+                format!("bash -c '</dev/tcp/127.0.0.1/{WAKU_PORT}'"),
+            ];
+            // The start period covers building the radio before it first runs
+            yaml.push_str(&format!(
+                "    healthcheck:\n      test: [ {} ]\n      interval: 5s\n      retries: 3\n      start_period: 10m\n",
+                list(&test)
+            ));
+        }
+
+        yaml
+    }
+}
+
+/// The services of a scenario: a boot node, the setup instances booting from it once it is up and the check
+/// starting once they all are
+fn services(scenario: &Scenario) -> Vec<Service> {
+    let boot_key = random_key();
+    let boot_node_address = format!(
+        "/dns4/{BOOT_NODE}/tcp/{WAKU_PORT}/p2p/{}",
+        peer_id(&boot_key)
+    );
+
+    let mut boot = Service::new(
+        BOOT_NODE.to_string(),
+        "--instance=boot".to_string(),
+        &[],
+        &BTreeMap::new(),
+    );
+    boot.environment
+        .insert("WAKU_NODE_KEY".to_string(), encode(boot_key.secret_bytes()));
+
+    let booting = |mut service: Service, depends_on: Vec<String>| {
+        service
+            .environment
+            .entry("BOOT_NODE_ADDRESSES".to_string())
+            .or_insert_with(|| boot_node_address.clone());
+        service.depends_on = depends_on;
+        service
+    };
+
+    let mut numbering: HashMap<&str, u32> = HashMap::new();
+    let mut instances = Vec::new();
+    for group in &scenario.instances {
+        for _ in 0..group.count {
+            let number = numbering.entry(&group.instance).or_default();
+            *number += 1;
+            let service = Service::new(
+                format!("{}-instance-{number}", group.instance.replace('_', "-")),
+                format!("--instance={}", group.instance),
+                &group.args,
+                &group.env,
+            );
+            instances.push(booting(service, vec![BOOT_NODE.to_string()]));
+        }
+    }
+
+    let check = &scenario.check;
+    let mut check = booting(
+        Service::new(
+            check_service(&check.check),
+            format!("--check={}", check.check),
+            &check.args,
+            &check.env,
+        ),
+        std::iter::once(BOOT_NODE.to_string())
+            .chain(instances.iter().map(|instance| instance.name.clone()))
+            .collect(),
+    );
+    check.healthcheck = false;
+
+    std::iter::once(boot)
+        .chain(instances)
+        .chain(std::iter::once(check))
+        .collect()
+}
+
+pub fn compose_file(scenario: &Scenario, scenario_path: &Path) -> String {
+    let mut yaml = format!(
+        "# Generated from {} by `cargo run -- compose`, keys are freshly generated on every run\nservices:\n",
+        scenario_path.display()
+    );
+    for service in services(scenario) {
+        yaml.push_str(&service.to_yaml(&scenario.env_file));
+    }
+
+    yaml
+}
+
+/// Writes the compose file of a scenario and, with `up`, runs it until the check exits. Returns the check's exit
+/// code, or 0 when only writing the file
+pub fn run_compose(scenario_path: &Path, output: &Path, up: bool) -> Result<i32, anyhow::Error> {
+    let scenario = Scenario::read(scenario_path)?;
+    fs::write(output, compose_file(&scenario, scenario_path))?;
+    info!(
+        "Wrote {} with {} setup instances and the {} check",
+        output.display(),
+        scenario
+            .instances
+            .iter()
+            .map(|group| group.count)
+            .sum::<u32>(),
+        scenario.check.check
+    );
+
+    if !up {
+        return Ok(0);
+    }
+
+    let status = Command::new("docker")
+        .arg("compose")
+        .arg("-f")
+        .arg(output)
+        .args(["up", "--build", "--abort-on-container-exit"])
+        .args(["--exit-code-from", &check_service(&scenario.check.check)])
+        .status()?;
+
+    match status.code() {
+        Some(code) => Ok(code),
+        None => bail!("docker compose was stopped by a signal"),
+    }
+}
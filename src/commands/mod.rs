@@ -0,0 +1,3 @@
+pub mod compose;
+pub mod orchestrate;
+pub mod report;
@@ -0,0 +1,243 @@
+use std::{env, process::Stdio, time::Duration};
+
+use anyhow::{anyhow, bail};
+use colored::Colorize;
+use hex::encode;
+use poi_radio_e2e_tests::utils::get_random_port;
+use rand::{thread_rng, Rng};
+use secp256k1::{PublicKey, Secp256k1, SecretKey};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, BufReader},
+    net::TcpStream,
+    process::{Child, Command},
+    signal,
+    time::{sleep, timeout, Instant},
+};
+use tracing::{debug, info, warn};
+
+/// How a test run is laid out: a boot node, `instances` setup instances booting from it and the check itself
+#[derive(Clone, Debug)]
+pub struct Orchestration {
+    pub check: String,
+    pub instance: String,
+    pub instances: u32,
+    pub boot_port: u16,
+    /// How long each node gets to start listening
+    pub ready_timeout: Duration,
+    /// How long the check gets to reach a verdict
+    pub timeout: Duration,
+}
+
+/// Set per node, never inherited from the orchestrator: a shared node key would give every node the boot node's
+/// peer identity, and a shared report path would have every node write over the same report
+const PER_NODE_ENV: [&str; 2] = ["WAKU_NODE_KEY", "RADIO_REPORT_PATH"];
+
+/// A child process of the orchestrator, running this same binary
+pub(crate) struct Node {
+    name: String,
+    waku_port: u16,
+    process: Child,
+}
+
+impl Node {
+    pub(crate) fn spawn(
+        name: &str,
+        args: &[&str],
+        envs: &[(&str, String)],
+    ) -> Result<Self, anyhow::Error> {
+        let waku_port = envs
+            .iter()
+            .find(|(key, _)| *key == "WAKU_PORT")
+            .ok_or_else(|| anyhow!("No Waku port for {name}"))?
+            .1
+            .parse()?;
+
+        let mut command = Command::new(env::current_exe()?);
+        for key in PER_NODE_ENV {
+            command.env_remove(key);
+        }
+        let mut process = command
+            .args(args)
+            .envs(envs.iter().map(|(key, value)| (key, value)))
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()?;
+
+        tokio::spawn(stream_logs(
+            name.to_string(),
+            process.stdout.take().unwrap(),
+        ));
+        tokio::spawn(stream_logs(
+            name.to_string(),
+            process.stderr.take().unwrap(),
+        ));
+        info!("Started {name} on Waku port {waku_port}");
+
+        Ok(Node {
+            name: name.to_string(),
+            waku_port,
+            process,
+        })
+    }
+
+    /// Waits for the node's Waku port to accept connections
+    pub(crate) async fn wait_until_ready(
+        &mut self,
+        ready_timeout: Duration,
+    ) -> Result<(), anyhow::Error> {
+        let started = Instant::now();
+        loop {
+            if TcpStream::connect(("127.0.0.1", self.waku_port))
+                .await
+                .is_ok()
+            {
+                info!("{} is ready", self.name);
+                return Ok(());
+            }
+            if let Some(status) = self.process.try_wait()? {
+                bail!("{} exited with {status} before it was ready", self.name);
+            }
+            if started.elapsed() > ready_timeout {
+                bail!(
+                    "{} was not ready after {}s",
+                    self.name,
+                    ready_timeout.as_secs()
+                );
+            }
+            sleep(Duration::from_secs(1)).await;
+        }
+    }
+
+    pub(crate) async fn stop(&mut self) {
+        if let Ok(Some(status)) = self.process.try_wait() {
+            debug!("{} already exited with {status}", self.name);
+            return;
+        }
+
+        match self.process.kill().await {
+            Ok(()) => info!("Stopped {}", self.name),
+            Err(e) => warn!("Could not stop {}: {e}", self.name),
+        }
+    }
+}
+
+/// Prints each line a node writes, prefixed with the node's name
+async fn stream_logs(name: String, output: impl AsyncRead + Unpin) {
+    let prefix = format!("[{name}]").cyan();
+    let mut lines = BufReader::new(output).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        println!("{prefix} {line}");
+    }
+}
+
+pub fn random_key() -> SecretKey {
+    let mut key = [0u8; 32];
+    thread_rng().fill(&mut key[..]);
+    SecretKey::from_slice(&key).expect("Error parsing secret key")
+}
+
+/// The libp2p peer ID of a secp256k1 node key: the base58 identity multihash of its protobuf encoded public key
+pub fn peer_id(node_key: &SecretKey) -> String {
+    let public_key = PublicKey::from_secret_key(&Secp256k1::new(), node_key).serialize();
+
+    // KeyType Secp256k1 in field 1, the compressed key in field 2
+    let mut encoded = vec![0x08, 0x02, 0x12, public_key.len() as u8];
+    encoded.extend_from_slice(&public_key);
+    let mut multihash = vec![0x00, encoded.len() as u8];
+    multihash.extend(encoded);
+
+    bs58::encode(multihash).into_string()
+}
+
+/// Starts every node, runs the check against them and tears everything down again. Returns the check's exit code
+pub async fn run_orchestrate(orchestration: &Orchestration) -> Result<i32, anyhow::Error> {
+    let mut nodes = Vec::new();
+    let verdict = tokio::select! {
+        verdict = orchestrate(orchestration, &mut nodes) => verdict,
+        _ = signal::ctrl_c() => Err(anyhow!("Interrupted")),
+    };
+
+    // The check is the last node, stop it first and the boot node last
+    for node in nodes.iter_mut().rev() {
+        node.stop().await;
+    }
+
+    verdict
+}
+
+async fn orchestrate(
+    orchestration: &Orchestration,
+    nodes: &mut Vec<Node>,
+) -> Result<i32, anyhow::Error> {
+    let boot_key = random_key();
+    let boot_node_address = format!(
+        "/ip4/127.0.0.1/tcp/{}/p2p/{}",
+        orchestration.boot_port,
+        peer_id(&boot_key)
+    );
+
+    let boot = Node::spawn(
+        "boot",
+        &["--instance", "boot"],
+        &[
+            ("PRIVATE_KEY", encode(random_key().secret_bytes())),
+            ("WAKU_NODE_KEY", encode(boot_key.secret_bytes())),
+            ("WAKU_PORT", orchestration.boot_port.to_string()),
+        ],
+    )?;
+    nodes.push(boot);
+    nodes[0]
+        .wait_until_ready(orchestration.ready_timeout)
+        .await?;
+
+    let booting_node = |name: &str, args: &[&str]| {
+        Node::spawn(
+            name,
+            args,
+            &[
+                ("PRIVATE_KEY", encode(random_key().secret_bytes())),
+                ("WAKU_PORT", get_random_port()),
+                ("BOOT_NODE_ADDRESSES", boot_node_address.clone()),
+            ],
+        )
+    };
+
+    for i in 1..=orchestration.instances {
+        nodes.push(booting_node(
+            &format!("{}-{i}", orchestration.instance),
+            &["--instance", &orchestration.instance],
+        )?);
+    }
+    for node in nodes.iter_mut().skip(1) {
+        node.wait_until_ready(orchestration.ready_timeout).await?;
+    }
+
+    // Only the check reports on the run, setup instances never write over its report
+    let mut check_envs = vec![
+        ("PRIVATE_KEY", encode(random_key().secret_bytes())),
+        ("WAKU_PORT", get_random_port()),
+        ("BOOT_NODE_ADDRESSES", boot_node_address.clone()),
+    ];
+    if let Ok(report_path) = env::var("RADIO_REPORT_PATH") {
+        check_envs.push(("RADIO_REPORT_PATH", report_path));
+    }
+    nodes.push(Node::spawn(
+        &orchestration.check,
+        &["--check", &orchestration.check],
+        &check_envs,
+    )?);
+    let check = nodes.last_mut().unwrap();
+    let status = timeout(orchestration.timeout, check.process.wait())
+        .await
+        .map_err(|_| {
+            anyhow!(
+                "{} reached no verdict within {}s",
+                orchestration.check,
+                orchestration.timeout.as_secs()
+            )
+        })??;
+
+    // A check killed by a signal has no exit code
+    Ok(status.code().unwrap_or(1))
+}
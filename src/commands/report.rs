@@ -0,0 +1,28 @@
+use std::path::{Path, PathBuf};
+
+use poi_radio_e2e_tests::reports::{read_report, write_report, ReportFormat, ReportRow};
+use tracing::info;
+
+/// Merges the JSON reports of several runs into one, so consensus health can be charted across them
+pub fn run_report(
+    inputs: &[PathBuf],
+    output: &Path,
+    format: Option<ReportFormat>,
+) -> Result<(), anyhow::Error> {
+    let mut rows: Vec<ReportRow> = Vec::new();
+    for input in inputs {
+        let report = read_report(input)?;
+        info!("Read {} rows from {}", report.len(), input.display());
+        rows.extend(report);
+    }
+
+    let format = format.unwrap_or_else(|| ReportFormat::from_path(output));
+    write_report(output, format, &rows)?;
+    info!(
+        "Wrote {} rows from {} runs to {}",
+        rows.len(),
+        inputs.len(),
+        output.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,221 @@
+use anyhow::anyhow;
+use graphcast_sdk::graphql::QueryError;
+use once_cell::sync::Lazy;
+use poi_radio_e2e_tests::HTTP_CLIENT;
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex as SyncMutex,
+    },
+    time::{Duration, Instant},
+};
+use tokio::time::sleep;
+use tracing::{debug, warn};
+
+/// Attempts per request, including the first one
+const MAX_ATTEMPTS: u32 = 3;
+/// Delay before the first retry, doubled for every retry after that
+const BASE_BACKOFF: Duration = Duration::from_millis(500);
+/// Consecutive failed requests after which an endpoint's circuit opens
+const FAILURE_THRESHOLD: u32 = 3;
+/// How long an open circuit fails requests fast before letting a probe request through
+const COOLDOWN: Duration = Duration::from_secs(15);
+
+static BREAKERS: Lazy<SyncMutex<HashMap<String, CircuitBreaker>>> =
+    Lazy::new(|| SyncMutex::new(HashMap::new()));
+
+/// Number of times any endpoint's circuit has opened
+static CIRCUIT_TRIPS: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Default)]
+struct CircuitBreaker {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+}
+
+/// Whether a request may be sent to an endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Admission {
+    Allowed,
+    /// The one request let through to find out whether the endpoint is back
+    Probe,
+    Rejected,
+}
+
+impl CircuitBreaker {
+    /// A closed circuit lets everything through. Once the cooldown of an open circuit is over a single request goes
+    /// through as a probe, while the rest keep failing fast for another cooldown. A failing probe opens the circuit
+    /// again and a successful one closes it. Should the probe never report back, the next cooldown lets another one
+    /// through
+    fn admit(&mut self) -> Admission {
+        match self.open_until {
+            Some(open_until) if Instant::now() >= open_until => {
+                self.open_until = Some(Instant::now() + COOLDOWN);
+                Admission::Probe
+            }
+            Some(_) => Admission::Rejected,
+            None => Admission::Allowed,
+        }
+    }
+
+    fn record_success(&mut self) {
+        self.consecutive_failures = 0;
+        self.open_until = None;
+    }
+
+    /// Returns whether this failure opened the circuit, which only a request reaching the threshold on a closed
+    /// circuit or a failed probe do. Requests admitted before the circuit opened that fail afterwards neither count
+    /// towards it nor hold it open for longer
+    fn record_failure(&mut self, admission: Admission) -> bool {
+        match (admission, self.open_until) {
+            (Admission::Probe, Some(_)) => {
+                self.open_until = Some(Instant::now() + COOLDOWN);
+                true
+            }
+            (Admission::Allowed, None) => {
+                self.consecutive_failures += 1;
+                if self.consecutive_failures < FAILURE_THRESHOLD {
+                    return false;
+                }
+
+                self.open_until = Some(Instant::now() + COOLDOWN);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+pub fn circuit_trips() -> u64 {
+    CIRCUIT_TRIPS.load(Ordering::SeqCst)
+}
+
+/// Timeouts, connection failures and server errors are worth another try, anything else would fail the same way again
+fn is_retryable(err: &reqwest::Error) -> bool {
+    match err.status() {
+        Some(status) => status.is_server_error(),
+        None => err.is_timeout() || err.is_connect() || err.is_request(),
+    }
+}
+
+/// Posts a JSON body to a graph node endpoint through the shared client, retrying with exponential backoff and
+/// failing fast while the endpoint's circuit is open
+pub async fn post_json<T: Serialize + ?Sized>(
+    endpoint: &str,
+    body: &T,
+) -> Result<reqwest::Response, QueryError> {
+    let admission = BREAKERS
+        .lock()
+        .unwrap()
+        .entry(endpoint.to_string())
+        .or_default()
+        .admit();
+    // A probe isn't retried, so it reports back within the cooldown that holds off the other requests
+    let max_attempts = match admission {
+        Admission::Allowed => MAX_ATTEMPTS,
+        Admission::Probe => 1,
+        Admission::Rejected => {
+            return Err(QueryError::Other(anyhow!(
+                "Circuit for {endpoint} is open, not sending the request"
+            )))
+        }
+    };
+
+    let mut backoff = BASE_BACKOFF;
+    let mut attempt = 1;
+    let result = loop {
+        let response = HTTP_CLIENT
+            .post(endpoint)
+            .json(body)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+
+        match response {
+            Err(err) if attempt < max_attempts && is_retryable(&err) => {
+                debug!("Request to {endpoint} failed (attempt {attempt}/{max_attempts}), retrying in {backoff:?}: {err}");
+                sleep(backoff).await;
+                backoff *= 2;
+                attempt += 1;
+            }
+            response => break response,
+        }
+    };
+
+    let mut breakers = BREAKERS.lock().unwrap();
+    let breaker = breakers.entry(endpoint.to_string()).or_default();
+    match &result {
+        Err(err) if is_retryable(err) => {
+            if breaker.record_failure(admission) {
+                CIRCUIT_TRIPS.fetch_add(1, Ordering::SeqCst);
+                if admission == Admission::Probe {
+                    warn!(
+                        "Circuit for {endpoint} opened again for {COOLDOWN:?} after a failed probe"
+                    );
+                } else {
+                    warn!(
+                        "Circuit for {endpoint} opened for {COOLDOWN:?} after {} failed requests",
+                        breaker.consecutive_failures
+                    );
+                }
+            }
+        }
+        // Anything else means the endpoint is up and answering
+        _ => breaker.record_success(),
+    }
+
+    Ok(result?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opens_once_on_reaching_the_threshold() {
+        let mut breaker = CircuitBreaker::default();
+        let admissions = (0..FAILURE_THRESHOLD + 2)
+            .map(|_| breaker.admit())
+            .collect::<Vec<_>>();
+        assert!(admissions.iter().all(|a| *a == Admission::Allowed));
+
+        let opened = admissions
+            .into_iter()
+            .map(|admission| breaker.record_failure(admission))
+            .collect::<Vec<_>>();
+        assert_eq!(opened.iter().filter(|opened| **opened).count(), 1);
+        assert!(opened[FAILURE_THRESHOLD as usize - 1]);
+        assert_eq!(breaker.consecutive_failures, FAILURE_THRESHOLD);
+    }
+
+    #[test]
+    fn late_failures_do_not_hold_the_circuit_open() {
+        let mut breaker = CircuitBreaker::default();
+        for _ in 0..FAILURE_THRESHOLD {
+            breaker.record_failure(Admission::Allowed);
+        }
+        let open_until = breaker.open_until;
+
+        assert!(!breaker.record_failure(Admission::Allowed));
+        assert_eq!(breaker.open_until, open_until);
+        assert_eq!(breaker.admit(), Admission::Rejected);
+    }
+
+    #[test]
+    fn a_failed_probe_opens_it_again() {
+        let mut breaker = CircuitBreaker {
+            consecutive_failures: FAILURE_THRESHOLD,
+            open_until: Some(Instant::now()),
+        };
+        assert_eq!(breaker.admit(), Admission::Probe);
+        assert!(breaker.record_failure(Admission::Probe));
+        assert_eq!(breaker.admit(), Admission::Rejected);
+
+        // A probe that reports back after the circuit was closed leaves it closed
+        breaker.record_success();
+        assert!(!breaker.record_failure(Admission::Probe));
+        assert_eq!(breaker.open_until, None);
+    }
+}
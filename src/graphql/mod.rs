@@ -1,12 +1,15 @@
+pub mod client;
+
+use client::post_json;
 use graphql_client::{GraphQLQuery, Response};
 use serde_derive::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use std::collections::HashMap;
 use tracing::debug;
 
-use poi_radio_e2e_tests::{BlockPointer, SubgraphStatus};
+use poi_radio_e2e_tests::{BlockPointer, ChainStatus, NetworkName, SubgraphHealth, SubgraphStatus};
 // Maybe later on move graphql to SDK as the queries are pretty standarded
 use graphcast_sdk::graphql::QueryError;
-use graphcast_sdk::NetworkName;
 
 /// Derived GraphQL Query to Proof of Indexing
 #[derive(GraphQLQuery, Serialize, Deserialize, Debug)]
@@ -38,30 +41,26 @@ pub struct BlockHashFromNumber;
 pub async fn perform_proof_of_indexing(
     graph_node_endpoint: String,
     variables: proof_of_indexing::Variables,
-) -> Result<reqwest::Response, reqwest::Error> {
+) -> Result<reqwest::Response, QueryError> {
     let request_body = ProofOfIndexing::build_query(variables);
-    let client = reqwest::Client::new();
-    client
-        .post(graph_node_endpoint)
-        .json(&request_body)
-        .send()
-        .await?
-        .error_for_status()
+    post_json(&graph_node_endpoint, &request_body).await
 }
 
 /// Construct GraphQL variables and parse result for Proof of Indexing.
-/// For other radio use cases, provide a function that returns a string
+/// For other radio use cases, provide a function that returns a string.
+/// Passing the indexer address makes graph node compute the POI bound to that indexer
 pub async fn query_graph_node_poi(
     graph_node_endpoint: String,
     ipfs_hash: String,
     block_hash: String,
     block_number: i64,
+    indexer: Option<String>,
 ) -> Result<String, QueryError> {
     let variables: proof_of_indexing::Variables = proof_of_indexing::Variables {
         subgraph: ipfs_hash.clone(),
         block_hash: block_hash.clone(),
         block_number,
-        indexer: None,
+        indexer,
     };
     let queried_result = perform_proof_of_indexing(graph_node_endpoint.clone(), variables).await?;
     let response_body: Response<proof_of_indexing::ResponseData> = queried_result.json().await?;
@@ -76,19 +75,186 @@ pub async fn query_graph_node_poi(
     }
 }
 
+/// Query graph node for the Proof of Indexing of several deployments at the same block in one request.
+/// graphql_client can only derive queries with a fixed set of fields, so this one is put together by hand
+/// with an aliased `proofOfIndexing` field per deployment
+pub async fn perform_proofs_of_indexing(
+    graph_node_endpoint: String,
+    ipfs_hashes: &[String],
+    block_hash: String,
+    block_number: i64,
+    indexer: Option<String>,
+) -> Result<reqwest::Response, QueryError> {
+    let mut params = vec![
+        "$blockNumber: Int!".to_string(),
+        "$blockHash: String!".to_string(),
+        "$indexer: String".to_string(),
+    ];
+    let mut fields = vec![];
+    let mut variables = json!({
+        "blockNumber": block_number,
+        "blockHash": block_hash,
+        "indexer": indexer,
+    });
+
+    for (i, ipfs_hash) in ipfs_hashes.iter().enumerate() {
+        params.push(format!("$subgraph{i}: String!"));
+        fields.push(format!(
+            "poi{i}: proofOfIndexing(subgraph: $subgraph{i}, blockNumber: $blockNumber, blockHash: $blockHash, indexer: $indexer)"
+        ));
+        variables[format!("subgraph{i}")] = json!(ipfs_hash);
+    }
+
+    let request_body = json!({
+        "query": format!(
+            "query ProofsOfIndexing({}) {{ {} }}",
+            params.join(", "),
+            fields.join(" ")
+        ),
+        "variables": variables,
+        "operationName": "ProofsOfIndexing",
+    });
+    post_json(&graph_node_endpoint, &request_body).await
+}
+
+/// Fetch the POIs of all given deployments at a block, keyed by deployment.
+/// Deployments graph node has no POI for are left out of the result
+pub async fn query_graph_node_pois(
+    graph_node_endpoint: String,
+    ipfs_hashes: &[String],
+    block_hash: String,
+    block_number: i64,
+    indexer: Option<String>,
+) -> Result<HashMap<String, String>, QueryError> {
+    let queried_result = perform_proofs_of_indexing(
+        graph_node_endpoint,
+        ipfs_hashes,
+        block_hash,
+        block_number,
+        indexer,
+    )
+    .await?;
+    // Only the aliased fields are ours to read, whatever else `data` holds is left alone
+    let response_body: Response<Value> = queried_result.json().await?;
+
+    let data = response_body
+        .data
+        .ok_or_else(|| QueryError::EmptyResponseError("Graph node POIs".to_string()))?;
+
+    Ok(ipfs_hashes
+        .iter()
+        .enumerate()
+        .filter_map(|(i, ipfs_hash)| {
+            data[format!("poi{i}")]
+                .as_str()
+                .map(|poi| (ipfs_hash.clone(), poi.to_string()))
+        })
+        .collect())
+}
+
+/// Query graph node for the hash of a block on a network
+pub async fn perform_block_hash_from_number(
+    graph_node_endpoint: String,
+    variables: block_hash_from_number::Variables,
+) -> Result<reqwest::Response, QueryError> {
+    let request_body = BlockHashFromNumber::build_query(variables);
+    post_json(&graph_node_endpoint, &request_body).await
+}
+
+/// Construct GraphQL variables and parse result for a block hash
+pub async fn query_block_hash_from_number(
+    graph_node_endpoint: String,
+    network: String,
+    block_number: i64,
+) -> Result<String, QueryError> {
+    let variables = block_hash_from_number::Variables {
+        network,
+        block_number,
+    };
+    let queried_result = perform_block_hash_from_number(graph_node_endpoint, variables).await?;
+    let response_body: Response<block_hash_from_number::ResponseData> =
+        queried_result.json().await?;
+
+    response_body
+        .data
+        .and_then(|data| data.block_hash_from_number)
+        .ok_or_else(|| QueryError::EmptyResponseError("Graph node block hash".to_string()))
+}
+
+/// Resolves block hashes through our own graph node, independently of the SDK. Each (network, block) is only
+/// queried once, unless a message disagrees with the cached hash, which could be from before a reorg
+pub struct BlockHashResolver {
+    graph_node_endpoint: String,
+    cache: HashMap<(NetworkName, u64), String>,
+}
+
+impl BlockHashResolver {
+    pub fn new(graph_node_endpoint: String) -> Self {
+        BlockHashResolver {
+            graph_node_endpoint,
+            cache: HashMap::new(),
+        }
+    }
+
+    pub async fn resolve(
+        &mut self,
+        network: &NetworkName,
+        block_number: u64,
+    ) -> Result<String, QueryError> {
+        if let Some(hash) = self.cached(network, block_number) {
+            return Ok(hash.clone());
+        }
+
+        let hash = query_block_hash_from_number(
+            self.graph_node_endpoint.clone(),
+            network.to_string(),
+            block_number.try_into().unwrap(),
+        )
+        .await?;
+        self.cache
+            .insert((network.clone(), block_number), hash.clone());
+        Ok(hash)
+    }
+
+    pub fn cached(&self, network: &NetworkName, block_number: u64) -> Option<&String> {
+        self.cache.get(&(network.clone(), block_number))
+    }
+
+    /// Checks a block hash against our graph node's, regardless of case and 0x prefix. Returns our hash if they
+    /// differ. A cached hash that differs is evicted and the block resolved once more before giving that verdict
+    pub async fn verify(
+        &mut self,
+        network: &NetworkName,
+        block_number: u64,
+        block_hash: &str,
+    ) -> Result<Option<String>, QueryError> {
+        let was_cached = self.cached(network, block_number).is_some();
+        let local_hash = self.resolve(network, block_number).await?;
+        if same_block_hash(&local_hash, block_hash) {
+            return Ok(None);
+        }
+        if !was_cached {
+            return Ok(Some(local_hash));
+        }
+
+        self.cache.remove(&(network.clone(), block_number));
+        let local_hash = self.resolve(network, block_number).await?;
+        Ok((!same_block_hash(&local_hash, block_hash)).then_some(local_hash))
+    }
+}
+
+fn same_block_hash(a: &str, b: &str) -> bool {
+    a.trim_start_matches("0x")
+        .eq_ignore_ascii_case(b.trim_start_matches("0x"))
+}
+
 /// Query graph node for Indexing Statuses
 pub async fn perform_indexing_statuses(
     graph_node_endpoint: String,
     variables: indexing_statuses::Variables,
-) -> Result<reqwest::Response, reqwest::Error> {
+) -> Result<reqwest::Response, QueryError> {
     let request_body = IndexingStatuses::build_query(variables);
-    let client = reqwest::Client::new();
-    client
-        .post(graph_node_endpoint)
-        .json(&request_body)
-        .send()
-        .await?
-        .error_for_status()
+    post_json(&graph_node_endpoint, &request_body).await
 }
 
 /// Construct GraphQL variables and parse result for Proof of Indexing.
@@ -101,61 +267,88 @@ pub async fn update_network_chainheads(
     let queried_result = perform_indexing_statuses(graph_node_endpoint.clone(), variables).await?;
     let response_body: Response<indexing_statuses::ResponseData> = queried_result.json().await?;
 
-    // subgraph (network, latest blocks)
-    let mut subgraph_network_blocks: HashMap<String, SubgraphStatus> = HashMap::new();
+    let data = response_body.data.ok_or(QueryError::IndexingError)?;
 
-    let updated_networks = response_body
-        .data
-        .map(|data| {
-            data.indexing_statuses
-                .into_iter()
-                .map(|status| {
-                    status
-                        .chains
-                        .into_iter()
-                        .map(|chain| {
-                            let network_name = chain.network.clone();
-                            let _chainhead_block = chain
-                                .chain_head_block
-                                .map(|blk| BlockPointer {
-                                    hash: blk.hash,
-                                    number: blk.number.as_str().parse::<u64>().unwrap_or_default(),
-                                })
-                                .map(|blk| {
-                                    if let Some(block) = network_map
-                                        .get_mut(&NetworkName::from_string(&network_name.clone()))
-                                    {
-                                        *block = blk.clone();
-                                    } else {
-                                        network_map
-                                            .entry(NetworkName::from_string(&network_name.clone()))
-                                            .or_insert(blk.clone());
-                                    };
-                                    blk
-                                });
-
-                            chain
-                                .latest_block
-                                .map(|blk| BlockPointer {
-                                    hash: blk.hash,
-                                    number: blk.number.as_str().parse::<u64>().unwrap_or_default(),
-                                })
-                                .map(|blk| {
-                                    subgraph_network_blocks
-                                        .entry(status.subgraph.clone())
-                                        .or_insert(SubgraphStatus {
-                                            network: chain.network.clone(),
-                                            block: blk.clone(),
-                                        });
-                                    blk
-                                });
-                            network_name
-                        })
-                        .collect::<String>()
+    let parse_block = |number: &str, hash: String| BlockPointer {
+        hash,
+        number: number.parse::<u64>().unwrap_or_default(),
+    };
+
+    // subgraph (chains with their latest blocks)
+    let mut subgraph_statuses: HashMap<String, SubgraphStatus> = HashMap::new();
+    for status in data.indexing_statuses {
+        let chains = status
+            .chains
+            .into_iter()
+            .filter_map(|chain| {
+                let network = NetworkName::from_string(&chain.network);
+                // Every chain the deployment indexes moves its network's chainhead
+                if let Some(blk) = chain.chain_head_block {
+                    network_map.insert(network.clone(), parse_block(&blk.number, blk.hash));
+                }
+
+                chain.latest_block.map(|blk| ChainStatus {
+                    network,
+                    latest_block: parse_block(&blk.number, blk.hash),
                 })
-                .collect::<Vec<String>>()
-        })
-        .ok_or(QueryError::IndexingError);
-    debug!("Updated networks: {:#?}", updated_networks);
-    Ok(subgraph_network_blocks)
+            })
+            .collect::<Vec<ChainStatus>>();
+
+        subgraph_statuses.insert(
+            status.subgraph,
+            SubgraphStatus {
+                chains,
+                synced: status.synced,
+                health: SubgraphHealth::from_string(&status.health),
+                fatal_error: status.fatal_error.map(|error| error.message),
+            },
+        );
+    }
+    debug!(
+        "Updated networks: {:?}",
+        network_map
+            .keys()
+            .map(|n| n.to_string())
+            .collect::<Vec<_>>()
+    );
+    Ok(subgraph_statuses)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLOCK_HASH: &str = "0xabc123";
+
+    #[tokio::test]
+    async fn verifies_cached_hashes_without_querying() {
+        // Nothing listens there, any query would fail
+        let mut resolver = BlockHashResolver::new("http://127.0.0.1:1/graphql".to_string());
+        resolver
+            .cache
+            .insert((NetworkName::Mainnet, 1), BLOCK_HASH.to_string());
+
+        assert_eq!(
+            resolver
+                .verify(&NetworkName::Mainnet, 1, "ABC123")
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn evicts_a_cached_hash_that_disagrees() {
+        let mut resolver = BlockHashResolver::new("http://127.0.0.1:1/graphql".to_string());
+        resolver
+            .cache
+            .insert((NetworkName::Mainnet, 1), BLOCK_HASH.to_string());
+
+        // The block is queried again rather than rejected on the cached hash, which fails here
+        assert!(resolver
+            .verify(&NetworkName::Mainnet, 1, "0xdef456")
+            .await
+            .is_err());
+        assert_eq!(resolver.cached(&NetworkName::Mainnet, 1), None);
+    }
 }
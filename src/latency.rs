@@ -0,0 +1,98 @@
+use chrono::Utc;
+use once_cell::sync::OnceCell;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex as SyncMutex},
+};
+
+/// Nonces below this are unix timestamps in seconds, the way the SDK stamps messages, anything above is taken to
+/// be in milliseconds. Seconds only reach it in the year 2286
+const MILLIS_NONCE_THRESHOLD: i64 = 10_000_000_000;
+
+/// When a message was sent in unix milliseconds, from the send time it carries in its nonce. Second precision
+/// nonces are taken as sent at the start of their second, which can be up to a second early, so latencies are only
+/// measured from them for payloads that don't carry their own send time
+pub fn sent_at_millis(nonce: i64) -> i64 {
+    match nonce {
+        nonce if nonce < MILLIS_NONCE_THRESHOLD => nonce * 1000,
+        nonce => nonce,
+    }
+}
+
+/// The sample that `p` percent of the samples are at or below, by nearest rank
+pub fn percentile(samples: &[u64], p: f64) -> Option<u64> {
+    if samples.is_empty() {
+        return None;
+    }
+
+    let mut sorted = samples.to_vec();
+    sorted.sort_unstable();
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.clamp(1, sorted.len()) - 1])
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LatencyPercentiles {
+    pub samples: usize,
+    pub p50: u64,
+    pub p95: u64,
+    pub p99: u64,
+}
+
+impl LatencyPercentiles {
+    pub fn of(samples: &[u64]) -> Option<Self> {
+        Some(LatencyPercentiles {
+            samples: samples.len(),
+            p50: percentile(samples, 50.0)?,
+            p95: percentile(samples, 95.0)?,
+            p99: percentile(samples, 99.0)?,
+        })
+    }
+}
+
+/// Publish-to-receive latencies in milliseconds of every message the handler accepted, per sender
+#[derive(Debug, Default)]
+pub struct LatencyLog {
+    pub per_sender: HashMap<String, Vec<u64>>,
+}
+
+impl LatencyLog {
+    pub fn record(&mut self, sender: &str, latency_ms: u64) {
+        self.per_sender
+            .entry(sender.to_string())
+            .or_default()
+            .push(latency_ms);
+    }
+
+    pub fn samples(&self) -> Vec<u64> {
+        self.per_sender.values().flatten().copied().collect()
+    }
+
+    /// Percentiles across all senders
+    pub fn percentiles(&self) -> Option<LatencyPercentiles> {
+        LatencyPercentiles::of(&self.samples())
+    }
+
+    pub fn sender_percentiles(&self) -> HashMap<String, LatencyPercentiles> {
+        self.per_sender
+            .iter()
+            .filter_map(|(sender, samples)| {
+                LatencyPercentiles::of(samples).map(|percentiles| (sender.clone(), percentiles))
+            })
+            .collect()
+    }
+}
+
+/// A global static (singleton) log of delivery latencies, populated by the attestation handler
+pub static LATENCIES: OnceCell<Arc<SyncMutex<LatencyLog>>> = OnceCell::new();
+
+/// Records how long a message took from its sender to us, if the log has been set up. The send time in milliseconds
+/// that v2 payloads carry is used over the nonce. Senders with a clock running ahead of ours would make for negative
+/// latencies, those count as delivered instantly
+pub fn record_latency(sender: &str, nonce: i64, sent_at: Option<i64>) {
+    if let Some(latencies) = LATENCIES.get() {
+        let sent_at = sent_at.unwrap_or_else(|| sent_at_millis(nonce));
+        let latency_ms = (Utc::now().timestamp_millis() - sent_at).max(0) as u64;
+        latencies.lock().unwrap().record(sender, latency_ms);
+    }
+}
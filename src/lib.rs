@@ -1,31 +1,45 @@
+pub mod latency;
+pub mod notifiers;
+pub mod rejections;
+pub mod reports;
 pub mod utils;
 
 use anyhow::anyhow;
 use colored::*;
 use ethers_contract::EthAbiType;
-use ethers_core::types::transaction::eip712::Eip712;
+use ethers_core::types::transaction::eip712::{EIP712Domain, Eip712, Eip712Error};
 use ethers_derive_eip712::*;
 use num_bigint::BigUint;
 use once_cell::sync::Lazy;
 use once_cell::sync::OnceCell;
-use prost::Message;
+use prost::{
+    bytes::{Buf, BufMut},
+    encoding::{decode_varint, encode_key, DecodeContext, WireType},
+    DecodeError, Message,
+};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::{
-    collections::HashMap,
+    collections::{BTreeSet, HashMap, HashSet},
+    env,
     error::Error,
     sync::{Arc, Mutex as SyncMutex},
+    time::Duration,
 };
 use tokio::sync::Mutex as AsyncMutex;
-use tracing::error;
 
 use graphcast_sdk::{
     graphcast_agent::{
         message_typing::{get_indexer_stake, GraphcastMessage},
         GraphcastAgent,
     },
-    graphql::{client_network::query_network_subgraph, client_registry::query_registry_indexer},
+    graphql::{
+        client_network::query_network_subgraph, client_registry::query_registry_indexer, QueryError,
+    },
+    NetworkName as SdkNetworkName,
 };
+use latency::record_latency;
+use rejections::{record_observed, record_rejection, Rejection, RejectionReason};
 
 #[derive(Eip712, EthAbiType, Clone, Message, Serialize, Deserialize)]
 #[eip712(
@@ -77,6 +91,314 @@ impl RadioPayloadMessage {
     }
 }
 
+/// Second revision of the radio payload, carrying context on how the NPOI was produced. Tags 1 and 2 line up with
+/// `RadioPayloadMessage`, and `version` is always set so a v2 payload never encodes to the same bytes as a v1 one.
+/// It is signed under its own EIP-712 domain version, so only receivers that know about v2 can verify it.
+#[derive(Eip712, EthAbiType, Clone, Message, Serialize, Deserialize)]
+#[eip712(
+    name = "Graphcast POI Radio",
+    version = "2",
+    chain_id = 1,
+    verifying_contract = "0xc944e90c64b2c07662a292be6244bdf05cda44a7"
+)]
+pub struct RadioPayloadMessageV2 {
+    #[prost(string, tag = "1")]
+    pub identifier: String,
+    #[prost(string, tag = "2")]
+    pub content: String,
+    #[prost(uint32, tag = "3")]
+    pub version: u32,
+    #[prost(string, tag = "4")]
+    pub block_hash: String,
+    #[prost(string, tag = "5")]
+    pub graph_node_version: String,
+    #[prost(string, tag = "6")]
+    pub poi_algorithm: String,
+    /// Chain the attestation is for when the SDK has no name for it and the message goes out as `unknown`, empty
+    /// otherwise
+    #[prost(string, tag = "7")]
+    pub network: String,
+    /// When the payload was sent in unix milliseconds, the SDK's nonce only has it to the second
+    #[prost(int64, tag = "8")]
+    pub sent_at: i64,
+}
+
+impl RadioPayloadMessageV2 {
+    pub const VERSION: u32 = 2;
+
+    pub fn new(
+        identifier: String,
+        content: String,
+        block_hash: String,
+        graph_node_version: String,
+        poi_algorithm: String,
+    ) -> Self {
+        RadioPayloadMessageV2 {
+            identifier,
+            content,
+            version: Self::VERSION,
+            block_hash,
+            graph_node_version,
+            poi_algorithm,
+            network: String::new(),
+            sent_at: 0,
+        }
+    }
+
+    pub fn with_sent_at(mut self, sent_at: i64) -> Self {
+        self.sent_at = sent_at;
+        self
+    }
+
+    pub fn with_network(mut self, network: String) -> Self {
+        self.network = network;
+        self
+    }
+}
+
+/// Receive-side view of a radio payload. Both `RadioPayloadMessage` and `DummyMsg` share tag 1 and only differ in the
+/// wire type of tag 2, so instead of failing to decode a `DummyMsg` we keep it as such. Any of tags 3 to 8 marks a
+/// `RadioPayloadMessageV2`. A payload with nothing past tag 1 is a `DummyMsg` whose `dummy_value` of 0 was left off
+/// the wire, as a radio payload always carries its NPOI in tag 2. Signatures are checked against whichever type was
+/// decoded, which lets the handler accept every payload version and tell a type confusion attempt from a payload
+/// that is just broken.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum InboundPayload {
+    Radio(RadioPayloadMessage),
+    RadioV2(RadioPayloadMessageV2),
+    /// Along with the bytes it was received as, empty for one that wasn't received
+    Dummy(DummyMsg, Vec<u8>),
+}
+
+impl Default for InboundPayload {
+    /// Decoding starts out from a `DummyMsg`, which is what the payload stays if tag 2 never shows up
+    fn default() -> Self {
+        InboundPayload::Dummy(DummyMsg::default(), Vec::new())
+    }
+}
+
+/// Takes the value of a field off `buf` exactly as it was encoded
+fn take_field_value<B: Buf>(wire_type: WireType, buf: &mut B) -> Result<Vec<u8>, DecodeError> {
+    let take_varint = |buf: &mut B, value: &mut Vec<u8>| -> Result<(), DecodeError> {
+        for _ in 0..10 {
+            if !buf.has_remaining() {
+                return Err(DecodeError::new("buffer underflow"));
+            }
+            let byte = buf.get_u8();
+            value.push(byte);
+            if byte & 0x80 == 0 {
+                return Ok(());
+            }
+        }
+        Err(DecodeError::new("invalid varint"))
+    };
+
+    let mut value = Vec::new();
+    let len = match wire_type {
+        WireType::Varint => {
+            take_varint(buf, &mut value)?;
+            return Ok(value);
+        }
+        WireType::LengthDelimited => {
+            take_varint(buf, &mut value)?;
+            decode_varint(&mut value.as_slice())? as usize
+        }
+        WireType::ThirtyTwoBit => 4,
+        WireType::SixtyFourBit => 8,
+        WireType::StartGroup | WireType::EndGroup => {
+            return Err(DecodeError::new("unexpected group in a radio payload"))
+        }
+    };
+    if buf.remaining() < len {
+        return Err(DecodeError::new("buffer underflow"));
+    }
+    value.extend_from_slice(&buf.copy_to_bytes(len));
+
+    Ok(value)
+}
+
+impl InboundPayload {
+    pub fn identifier(&self) -> &str {
+        match self {
+            InboundPayload::Radio(payload) => &payload.identifier,
+            InboundPayload::RadioV2(payload) => &payload.identifier,
+            InboundPayload::Dummy(payload, _) => &payload.identifier,
+        }
+    }
+
+    /// EIP-712 type hash of the payload type that was decoded
+    pub fn variant_type_hash(&self) -> Result<[u8; 32], Eip712Error> {
+        match self {
+            InboundPayload::Radio(_) => RadioPayloadMessage::type_hash(),
+            InboundPayload::RadioV2(_) => RadioPayloadMessageV2::type_hash(),
+            InboundPayload::Dummy(..) => DummyMsg::type_hash(),
+        }
+    }
+
+    /// Radio payload schema version, `None` for payloads that aren't radio payloads at all
+    pub fn version(&self) -> Option<u32> {
+        match self {
+            InboundPayload::Radio(_) => Some(1),
+            InboundPayload::RadioV2(payload) => Some(payload.version),
+            InboundPayload::Dummy(..) => None,
+        }
+    }
+
+    /// When the payload was sent in unix milliseconds, if it says so itself
+    pub fn sent_at(&self) -> Option<i64> {
+        match self {
+            InboundPayload::RadioV2(payload) if payload.sent_at > 0 => Some(payload.sent_at),
+            _ => None,
+        }
+    }
+
+    /// Re-types a received message as a radio message, if that's what its payload turned out to be.
+    /// Later payload versions are reduced to the v1 fields, which is all that attestations are aggregated on
+    pub fn into_radio_message(
+        msg: GraphcastMessage<InboundPayload>,
+    ) -> Option<GraphcastMessage<RadioPayloadMessage>> {
+        let mut network = msg.network;
+        let payload = match msg.payload {
+            Some(InboundPayload::Radio(payload)) => payload,
+            Some(InboundPayload::RadioV2(payload)) => {
+                // Only a message the SDK sent as unknown has its chain named in the payload
+                if !payload.network.is_empty()
+                    && NetworkName::from_string(&network) == NetworkName::Unknown
+                {
+                    network = payload.network;
+                }
+                RadioPayloadMessage::new(payload.identifier, payload.content)
+            }
+            _ => return None,
+        };
+
+        Some(GraphcastMessage {
+            identifier: msg.identifier,
+            payload: Some(payload),
+            nonce: msg.nonce,
+            network,
+            block_number: msg.block_number,
+            block_hash: msg.block_hash,
+            signature: msg.signature,
+        })
+    }
+}
+
+impl Message for InboundPayload {
+    fn encode_raw<B: BufMut>(&self, buf: &mut B) {
+        match self {
+            InboundPayload::Radio(payload) => payload.encode_raw(buf),
+            InboundPayload::RadioV2(payload) => payload.encode_raw(buf),
+            InboundPayload::Dummy(payload, raw) if raw.is_empty() => payload.encode_raw(buf),
+            InboundPayload::Dummy(_, raw) => buf.put_slice(raw),
+        }
+    }
+
+    fn merge_field<B: Buf>(
+        &mut self,
+        tag: u32,
+        wire_type: WireType,
+        buf: &mut B,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError> {
+        // A length-delimited tag 2 can only come from a radio payload, and only v2 radio payloads have anything
+        // past tag 2
+        let retyped = match (tag, wire_type, &*self) {
+            (2, WireType::LengthDelimited, InboundPayload::Dummy(payload, _)) => {
+                Some(InboundPayload::Radio(RadioPayloadMessage::new(
+                    payload.identifier.clone(),
+                    String::new(),
+                )))
+            }
+            (3..=8, _, InboundPayload::Dummy(payload, _)) => {
+                Some(InboundPayload::RadioV2(RadioPayloadMessageV2 {
+                    identifier: payload.identifier.clone(),
+                    ..Default::default()
+                }))
+            }
+            (3..=8, _, InboundPayload::Radio(payload)) => {
+                Some(InboundPayload::RadioV2(RadioPayloadMessageV2 {
+                    identifier: payload.identifier.clone(),
+                    content: payload.content.clone(),
+                    ..Default::default()
+                }))
+            }
+            _ => None,
+        };
+        if let Some(retyped) = retyped {
+            *self = retyped;
+        }
+
+        match self {
+            InboundPayload::Radio(payload) => payload.merge_field(tag, wire_type, buf, ctx),
+            InboundPayload::RadioV2(payload) => payload.merge_field(tag, wire_type, buf, ctx),
+            InboundPayload::Dummy(payload, raw) => {
+                let value = take_field_value(wire_type, buf)?;
+                encode_key(tag, wire_type, raw);
+                raw.extend_from_slice(&value);
+                payload.merge_field(tag, wire_type, &mut value.as_slice(), ctx)
+            }
+        }
+    }
+
+    fn encoded_len(&self) -> usize {
+        match self {
+            InboundPayload::Radio(payload) => payload.encoded_len(),
+            InboundPayload::RadioV2(payload) => payload.encoded_len(),
+            InboundPayload::Dummy(payload, raw) if raw.is_empty() => payload.encoded_len(),
+            InboundPayload::Dummy(_, raw) => raw.len(),
+        }
+    }
+
+    fn clear(&mut self) {
+        *self = InboundPayload::default();
+    }
+}
+
+impl Eip712 for InboundPayload {
+    type Error = Eip712Error;
+
+    fn domain_separator(&self) -> Result<[u8; 32], Self::Error> {
+        match self {
+            InboundPayload::Radio(payload) => payload.domain_separator(),
+            InboundPayload::RadioV2(payload) => payload.domain_separator(),
+            InboundPayload::Dummy(payload, _) => payload.domain_separator(),
+        }
+    }
+
+    fn domain(&self) -> Result<EIP712Domain, Self::Error> {
+        match self {
+            InboundPayload::Radio(payload) => payload.domain(),
+            InboundPayload::RadioV2(payload) => payload.domain(),
+            InboundPayload::Dummy(payload, _) => payload.domain(),
+        }
+    }
+
+    /// Each payload type has a type hash of its own, which isn't known without a decoded payload to tell the type.
+    /// Use `variant_type_hash` instead
+    fn type_hash() -> Result<[u8; 32], Self::Error> {
+        Err(Eip712Error::Message(
+            "the type hash depends on the payload type that was decoded".to_string(),
+        ))
+    }
+
+    fn struct_hash(&self) -> Result<[u8; 32], Self::Error> {
+        match self {
+            InboundPayload::Radio(payload) => payload.struct_hash(),
+            InboundPayload::RadioV2(payload) => payload.struct_hash(),
+            InboundPayload::Dummy(payload, _) => payload.struct_hash(),
+        }
+    }
+
+    fn encode_eip712(&self) -> Result<[u8; 32], Self::Error> {
+        match self {
+            InboundPayload::Radio(payload) => payload.encode_eip712(),
+            InboundPayload::RadioV2(payload) => payload.encode_eip712(),
+            InboundPayload::Dummy(payload, _) => payload.encode_eip712(),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Network {
     pub name: NetworkName,
@@ -89,10 +411,72 @@ pub struct BlockPointer {
     pub number: u64,
 }
 
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum SubgraphHealth {
+    Healthy,
+    /// Graph node ran into non-fatal errors, the deployment keeps indexing but its data may be off
+    Unhealthy,
+    Failed,
+}
+
+impl SubgraphHealth {
+    pub fn from_string(health: &str) -> Self {
+        match health {
+            "healthy" => SubgraphHealth::Healthy,
+            "unhealthy" => SubgraphHealth::Unhealthy,
+            _ => SubgraphHealth::Failed,
+        }
+    }
+}
+
+impl fmt::Display for SubgraphHealth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let health = match self {
+            SubgraphHealth::Healthy => "healthy",
+            SubgraphHealth::Unhealthy => "unhealthy",
+            SubgraphHealth::Failed => "failed",
+        };
+
+        write!(f, "{health}")
+    }
+}
+
+/// Where a deployment is at on one of the chains it indexes
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ChainStatus {
+    pub network: NetworkName,
+    pub latest_block: BlockPointer,
+}
+
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct SubgraphStatus {
-    pub network: String,
-    pub block: BlockPointer,
+    /// Every chain the deployment indexes, in the order graph node reports them
+    pub chains: Vec<ChainStatus>,
+    pub synced: bool,
+    pub health: SubgraphHealth,
+    pub fatal_error: Option<String>,
+}
+
+impl SubgraphStatus {
+    /// The chain the deployment's POIs are attested on. Graph node resolves a POI against a single block pointer,
+    /// so a deployment indexing several chains is attested on the first one it reports, the others only move chainheads
+    pub fn primary_chain(&self) -> Option<&ChainStatus> {
+        self.chains.first()
+    }
+
+    pub fn is_multi_chain(&self) -> bool {
+        self.chains.len() > 1
+    }
+
+    /// Why the radio shouldn't attest to this deployment's POIs, if there is a reason
+    pub fn skip_reason(&self) -> Option<String> {
+        match (&self.health, &self.fatal_error) {
+            (SubgraphHealth::Healthy, _) if self.synced => None,
+            (SubgraphHealth::Healthy, _) => Some("deployment is not synced".to_string()),
+            (health, Some(error)) => Some(format!("deployment is {health}: {error}")),
+            (health, None) => Some(format!("deployment is {health}")),
+        }
+    }
 }
 
 pub struct BlockClock {
@@ -100,7 +484,7 @@ pub struct BlockClock {
     pub compare_block: u64,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum NetworkName {
     Goerli,
     Mainnet,
@@ -112,10 +496,27 @@ pub enum NetworkName {
     Polygon,
     Celo,
     Optimism,
+    /// A chain that isn't built in, registered through `RADIO_NETWORKS`
+    Custom(String),
     Unknown,
 }
 
 impl NetworkName {
+    /// Every variant other than `Custom`, each of which has a `graphcast_sdk::NetworkName` counterpart
+    pub const BUILT_IN: [NetworkName; 11] = [
+        NetworkName::Goerli,
+        NetworkName::Mainnet,
+        NetworkName::Gnosis,
+        NetworkName::Hardhat,
+        NetworkName::ArbitrumOne,
+        NetworkName::ArbitrumGoerli,
+        NetworkName::Avalanche,
+        NetworkName::Polygon,
+        NetworkName::Celo,
+        NetworkName::Optimism,
+        NetworkName::Unknown,
+    ];
+
     pub fn from_string(name: &str) -> Self {
         match name {
             "goerli" => NetworkName::Goerli,
@@ -128,7 +529,63 @@ impl NetworkName {
             "polygon" => NetworkName::Polygon,
             "celo" => NetworkName::Celo,
             "optimism" => NetworkName::Optimism,
-            _ => NetworkName::Unknown,
+            "" | "unknown" => NetworkName::Unknown,
+            name => NetworkName::Custom(name.to_string()),
+        }
+    }
+}
+
+/// The crate's `NetworkName` is the canonical one, the SDK's is only used at the boundary when sending.
+/// Both conversions match on variants rather than names, so a network added on either side fails to compile
+/// until it is mapped here instead of silently becoming `Unknown`.
+impl From<SdkNetworkName> for NetworkName {
+    fn from(network: SdkNetworkName) -> Self {
+        match network {
+            SdkNetworkName::Goerli => NetworkName::Goerli,
+            SdkNetworkName::Mainnet => NetworkName::Mainnet,
+            SdkNetworkName::Gnosis => NetworkName::Gnosis,
+            SdkNetworkName::Hardhat => NetworkName::Hardhat,
+            SdkNetworkName::ArbitrumOne => NetworkName::ArbitrumOne,
+            SdkNetworkName::ArbitrumGoerli => NetworkName::ArbitrumGoerli,
+            SdkNetworkName::Avalanche => NetworkName::Avalanche,
+            SdkNetworkName::Polygon => NetworkName::Polygon,
+            SdkNetworkName::Celo => NetworkName::Celo,
+            SdkNetworkName::Optimism => NetworkName::Optimism,
+            SdkNetworkName::Unknown => NetworkName::Unknown,
+        }
+    }
+}
+
+impl NetworkName {
+    /// The SDK network a message for this chain goes out on. A chain the SDK has no name for goes out as `unknown`,
+    /// along with its own name for the v2 payload to carry, so receivers can put it back
+    pub fn to_wire(&self) -> (SdkNetworkName, Option<String>) {
+        match SdkNetworkName::try_from(self) {
+            Ok(network) => (network, None),
+            Err(_) => (SdkNetworkName::Unknown, Some(self.to_string())),
+        }
+    }
+}
+
+impl TryFrom<&NetworkName> for SdkNetworkName {
+    type Error = anyhow::Error;
+
+    fn try_from(network: &NetworkName) -> Result<Self, Self::Error> {
+        match network {
+            NetworkName::Goerli => Ok(SdkNetworkName::Goerli),
+            NetworkName::Mainnet => Ok(SdkNetworkName::Mainnet),
+            NetworkName::Gnosis => Ok(SdkNetworkName::Gnosis),
+            NetworkName::Hardhat => Ok(SdkNetworkName::Hardhat),
+            NetworkName::ArbitrumOne => Ok(SdkNetworkName::ArbitrumOne),
+            NetworkName::ArbitrumGoerli => Ok(SdkNetworkName::ArbitrumGoerli),
+            NetworkName::Avalanche => Ok(SdkNetworkName::Avalanche),
+            NetworkName::Polygon => Ok(SdkNetworkName::Polygon),
+            NetworkName::Celo => Ok(SdkNetworkName::Celo),
+            NetworkName::Optimism => Ok(SdkNetworkName::Optimism),
+            NetworkName::Unknown => Ok(SdkNetworkName::Unknown),
+            NetworkName::Custom(name) => Err(anyhow!(
+                "network {name} has no graphcast_sdk::NetworkName counterpart"
+            )),
         }
     }
 }
@@ -136,6 +593,7 @@ impl NetworkName {
 impl fmt::Display for NetworkName {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let name = match self {
+            NetworkName::Custom(name) => name.as_str(),
             NetworkName::Goerli => "goerli",
             NetworkName::Mainnet => "mainnet",
             NetworkName::Gnosis => "gnosis",
@@ -153,7 +611,8 @@ impl fmt::Display for NetworkName {
     }
 }
 
-pub static NETWORKS: Lazy<Vec<Network>> = Lazy::new(|| {
+/// Networks the radio knows about out of the box, along with how often (in blocks) it attests on them
+fn default_networks() -> Vec<Network> {
     vec![
         Network {
             name: NetworkName::from_string("goerli"),
@@ -196,6 +655,73 @@ pub static NETWORKS: Lazy<Vec<Network>> = Lazy::new(|| {
             interval: 5,
         },
     ]
+}
+
+/// Parses a network registry in the `name:interval,name:interval` format, e.g. `goerli:2,my-chain:10`.
+/// Names that aren't built in are registered as custom networks
+pub fn parse_networks(config: &str) -> Result<Vec<Network>, anyhow::Error> {
+    config
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (name, interval) = entry
+                .split_once(':')
+                .ok_or_else(|| anyhow!("Expected a name:interval pair, got '{entry}'"))?;
+            let interval = interval
+                .trim()
+                .parse::<u64>()
+                .map_err(|e| anyhow!("Invalid interval for network {name}: {e}"))?;
+            if interval == 0 {
+                return Err(anyhow!("Interval for network {name} must be at least 1"));
+            }
+
+            Ok(Network {
+                name: NetworkName::from_string(name.trim()),
+                interval,
+            })
+        })
+        .collect()
+}
+
+/// Checks the `RADIO_NETWORKS` env var parses, so a bad configuration is reported at startup rather than the first
+/// time the registry is used
+pub fn validate_networks_config() -> Result<(), anyhow::Error> {
+    match env::var("RADIO_NETWORKS") {
+        Ok(config) => parse_networks(&config).map(|_| ()),
+        Err(_) => Ok(()),
+    }
+}
+
+/// The network registry. Built in networks can have their interval overridden and new chains can be added
+/// through the `RADIO_NETWORKS` env var, which is read the first time the registry is used
+pub static NETWORKS: Lazy<Vec<Network>> = Lazy::new(|| {
+    let mut networks = default_networks();
+
+    if let Ok(config) = env::var("RADIO_NETWORKS") {
+        let configured = parse_networks(&config).expect("RADIO_NETWORKS is validated at startup");
+        for network in configured {
+            match networks.iter_mut().find(|n| n.name == network.name) {
+                Some(existing) => existing.interval = network.interval,
+                None => networks.push(network),
+            }
+        }
+    }
+
+    networks
+});
+
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+const HTTP_CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// One client for every HTTP request the radio makes itself, so connections are pooled and reused between calls.
+/// Requests that need a tighter bound than `HTTP_TIMEOUT` set their own
+pub static HTTP_CLIENT: Lazy<reqwest::Client> = Lazy::new(|| {
+    reqwest::Client::builder()
+        .timeout(HTTP_TIMEOUT)
+        .connect_timeout(HTTP_CONNECT_TIMEOUT)
+        .build()
+        .expect("Could not build the HTTP client")
 });
 
 pub type RemoteAttestationsMap = HashMap<String, HashMap<u64, Vec<Attestation>>>;
@@ -215,6 +741,72 @@ pub type MessagesArc = Arc<SyncMutex<Vec<(String, GraphcastMessage<RadioPayloadM
 /// it is not allowed in the handler itself.
 pub static MESSAGES: MessagesVec = OnceCell::new();
 
+/// An attestation this instance has sent, kept so checks can assert on the sending side too
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SentMessage {
+    pub identifier: String,
+    pub network: NetworkName,
+    pub block_number: u64,
+    pub npoi: String,
+}
+
+/// A global static (singleton) log of the attestations sent by this instance
+pub static SENT_MESSAGES: OnceCell<Arc<SyncMutex<Vec<SentMessage>>>> = OnceCell::new();
+
+/// A change to the content topics of this instance, made when its allocations changed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TopicUpdate {
+    pub opened: Vec<String>,
+    pub closed: Vec<String>,
+    /// The agent's content identifiers once updated
+    pub topics: Vec<String>,
+    /// Unix timestamp in milliseconds
+    pub updated_at: i64,
+    /// How many attestations had been sent before the update, those after it only go out on the new topics
+    pub sent_before: usize,
+}
+
+/// A global static (singleton) log of the content topic updates of this instance
+pub static TOPIC_UPDATES: OnceCell<Arc<SyncMutex<Vec<TopicUpdate>>>> = OnceCell::new();
+
+/// Where a deployment's NPOIs departed from the stake-weighted consensus, found by bisecting a critical comparison
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Divergence {
+    pub identifier: String,
+    /// Earliest block with remote attestations on which our NPOI differs from the consensus
+    pub first_diverged_block: u64,
+    /// Latest block before that on which our NPOI still matched, if there were remote attestations for any
+    pub last_matching_block: Option<u64>,
+}
+
+/// A global static (singleton) log of the divergences this instance has bisected
+pub static DIVERGENCES: OnceCell<Arc<SyncMutex<Vec<Divergence>>>> = OnceCell::new();
+
+/// What the latest round of comparing attestations aggregated from the received messages
+#[derive(Clone, Debug, Default)]
+pub struct LastRound {
+    /// Counts the rounds compared so far, so readers can tell a new round from the one they last looked at
+    pub round: u64,
+    pub attestation_block: u64,
+    pub remote: RemoteAttestationsMap,
+    pub local: LocalAttestationsMap,
+    /// Network the received messages named for each subgraph and block
+    pub networks: HashMap<(String, u64), String>,
+    /// How many stored messages `process_messages` aggregated this round, and how long that took
+    pub aggregated: usize,
+    pub aggregation_time: Duration,
+}
+
+/// A global static (singleton) copy of the latest round, so it can be looked at outside the radio loop without
+/// aggregating the received messages again
+pub static LAST_ROUND: OnceCell<Arc<SyncMutex<LastRound>>> = OnceCell::new();
+
+pub type PayloadVersionsMap = HashMap<String, BTreeSet<u32>>;
+
+/// Every radio payload version each sender has attested with. Messages are stored as v1 once validated,
+/// so this is the only place a mixed-version network is visible after the handler.
+pub static PAYLOAD_VERSIONS: OnceCell<Arc<SyncMutex<PayloadVersionsMap>>> = OnceCell::new();
+
 /// Updates the `blocks` HashMap to include the new attestation.
 pub fn update_blocks(
     block_number: u64,
@@ -252,18 +844,56 @@ pub async fn process_messages(
     messages: MessagesArc,
     registry_subgraph: &str,
     network_subgraph: &str,
+    multi_chain_deployments: &HashMap<String, NetworkName>,
 ) -> Result<RemoteAttestationsMap, anyhow::Error> {
     let mut remote_attestations: RemoteAttestationsMap = HashMap::new();
-    let messages = AsyncMutex::new(messages.lock().unwrap());
+    // Aggregating only reads the store, and working off a snapshot keeps the handler from waiting on the queries
+    let messages = messages.lock().unwrap().clone();
+
+    // Senders are resolved against the registry as it is now rather than when their messages arrived, so a key the
+    // registry has since moved its Indexer away from no longer counts. Its messages stay in the store, they were
+    // valid when received and it's up to the receive path to reject what the key sends from now on
+    let mut stakes: HashMap<String, Option<BigUint>> = HashMap::new();
+    for sender in messages
+        .iter()
+        .map(|(sender, _)| sender.clone())
+        .collect::<HashSet<String>>()
+    {
+        let indexer =
+            match query_registry_indexer(registry_subgraph.to_string(), sender.clone()).await {
+                Ok(indexer) => indexer,
+                Err(QueryError::Transport(e)) => return Err(e.into()),
+                Err(_) => {
+                    stakes.insert(sender, None);
+                    continue;
+                }
+            };
+        let stake = get_indexer_stake(indexer, network_subgraph).await?;
+        stakes.insert(sender, Some(stake));
+    }
+
+    // A sender's stake counts once per block, however many times it attested to it
+    let mut counted: HashSet<(String, u64, String)> = HashSet::new();
+
+    // The sender was recovered by the handler against the payload version it was signed with,
+    // recovering it again from the stored v1 payload would not give the same address for v2 messages
+    for (sender, msg) in messages.iter() {
+        // Block numbers of a multi-chain deployment are only comparable on the chain we attest it on
+        if let Some(network) = multi_chain_deployments.get(&msg.identifier) {
+            if NetworkName::from_string(&msg.network) != *network {
+                continue;
+            }
+        }
 
-    for (_, msg) in messages.lock().await.iter() {
         let radio_msg = &msg.payload.clone().unwrap();
-        let sender = msg.recover_sender_address()?;
-        let sender_stake = get_indexer_stake(
-            query_registry_indexer(registry_subgraph.to_string(), sender.clone()).await?,
-            network_subgraph,
-        )
-        .await?;
+        let sender = sender.clone();
+        let sender_stake = match stakes.get(&sender) {
+            Some(Some(stake)) => stake.clone(),
+            _ => continue,
+        };
+        if !counted.insert((msg.identifier.clone(), msg.block_number, sender.clone())) {
+            continue;
+        }
 
         // Check if there are existing attestations for the block
         let blocks = remote_attestations
@@ -278,9 +908,7 @@ pub async fn process_messages(
         match existing_attestation {
             Some(existing_attestation) => {
                 existing_attestation.stake_weight += sender_stake;
-                if !existing_attestation.senders.contains(&sender) {
-                    existing_attestation.senders.push(sender);
-                }
+                existing_attestation.senders.push(sender);
             }
             None => {
                 attestations.push(Attestation::new(
@@ -331,6 +959,14 @@ impl Attestation {
     }
 }
 
+/// The NPOI with the highest total stake-weight among the attestations for a block
+pub fn most_attested_npoi(attestations: &[Attestation]) -> Option<&String> {
+    attestations
+        .iter()
+        .max_by(|a, b| a.stake_weight.cmp(&b.stake_weight))
+        .map(|attestation| &attestation.npoi)
+}
+
 /// Saves NPOIs that we've generated locally, in order to compare them with remote ones later
 pub fn save_local_attestation(
     local_attestations: &mut LocalAttestationsMap,
@@ -357,17 +993,65 @@ pub fn save_local_attestation(
 
 /// Custom callback for handling the validated GraphcastMessage, in this case we only save the messages to a local store
 /// to process them at a later time. This is required because for the processing we use async operations which are not allowed
-/// in the handler.
-pub fn attestation_handler() -> impl Fn(Result<GraphcastMessage<RadioPayloadMessage>, anyhow::Error>)
-{
-    |msg: Result<GraphcastMessage<RadioPayloadMessage>, anyhow::Error>| match msg {
-        Ok(msg) => {
-            let sender = msg.recover_sender_address().unwrap();
+/// in the handler. Messages that don't make it into the store are classified and recorded in the rejection log.
+pub fn attestation_handler(
+    self_address: String,
+) -> impl Fn(Result<GraphcastMessage<InboundPayload>, anyhow::Error>) {
+    move |msg: Result<GraphcastMessage<InboundPayload>, anyhow::Error>| {
+        record_observed();
 
-            MESSAGES.get().unwrap().lock().unwrap().push((sender, msg));
-        }
-        Err(err) => {
-            error!("{}", err);
+        match msg {
+            Ok(msg) => match msg.recover_sender_address() {
+                Ok(sender) if sender.eq_ignore_ascii_case(&self_address) => {
+                    record_rejection(Rejection::new(
+                        Some(sender),
+                        RejectionReason::SelfMessage,
+                        "Received a message sent by this instance".to_string(),
+                    ));
+                }
+                Ok(sender) => match &msg.payload {
+                    Some(payload @ (InboundPayload::Radio(_) | InboundPayload::RadioV2(_))) => {
+                        if let Some(versions) = PAYLOAD_VERSIONS.get() {
+                            versions
+                                .lock()
+                                .unwrap()
+                                .entry(sender.clone())
+                                .or_default()
+                                .insert(payload.version().unwrap());
+                        }
+                        record_latency(&sender, msg.nonce, payload.sent_at());
+                        let msg = InboundPayload::into_radio_message(msg).unwrap();
+                        MESSAGES.get().unwrap().lock().unwrap().push((sender, msg));
+                    }
+                    Some(InboundPayload::Dummy(_, raw)) => {
+                        record_rejection(
+                            Rejection::new(
+                                Some(sender),
+                                RejectionReason::TypeConfusion,
+                                "Received a DummyMsg in place of a RadioPayloadMessage".to_string(),
+                            )
+                            .with_payload(raw.clone()),
+                        );
+                    }
+                    None => {
+                        record_rejection(Rejection::new(
+                            Some(sender),
+                            RejectionReason::DecodeFailure,
+                            "Received a message without a payload".to_string(),
+                        ));
+                    }
+                },
+                Err(err) => {
+                    record_rejection(Rejection::new(
+                        None,
+                        RejectionReason::BadSignature,
+                        err.to_string(),
+                    ));
+                }
+            },
+            Err(err) => {
+                record_rejection(Rejection::from_error(&err));
+            }
         }
     }
 }
@@ -399,53 +1083,8 @@ pub async fn compare_attestations(
     let local = local.lock().await;
 
     // Iterate & compare
-    if let Some((ipfs_hash, blocks)) = local.iter().next() {
-        let attestations = blocks.get(&attestation_block);
-        match attestations {
-            Some(local_attestation) => {
-                let remote_blocks = remote.get(ipfs_hash);
-                match remote_blocks {
-                    Some(remote_blocks) => {
-                        let remote_attestations = remote_blocks.get(&attestation_block);
-
-                        match remote_attestations {
-                            Some(remote_attestations) => {
-                                let mut remote_attestations = remote_attestations.clone();
-
-                        remote_attestations
-                        .sort_by(|a, b| a.stake_weight.partial_cmp(&b.stake_weight).unwrap());
-
-                    let most_attested_npoi = &remote_attestations.last().unwrap().npoi;
-                    if most_attested_npoi == &local_attestation.npoi {
-                        return Ok(format!(
-                            "POIs match for subgraph {ipfs_hash} on block {attestation_block}!"
-                        ));
-                    } else {
-                        return Err(CompareError::Critical(format!(
-                            "POIs don't match for subgraph {ipfs_hash} on block {attestation_block}!"
-                        )
-                        .red()
-                        .bold().to_string()));
-                    }
-                            },
-                            None => {
-                                return Err(CompareError::NonCritical(format!(
-                                    "No record for subgraph {ipfs_hash} on block {attestation_block} found in remote attestations"
-                                )
-                                .yellow().to_string()
-                               ));
-                            }
-                        }
-                    }
-                    None => {
-                        return Err(CompareError::NonCritical(format!("No attestations for subgraph {ipfs_hash} on block {attestation_block} found in remote attestations store. Continuing...", ).yellow().to_string()))
-                    }
-                }
-            }
-            None => {
-                return Err(CompareError::NonCritical(format!("No attestation for subgraph {ipfs_hash} on block {attestation_block} found in local attestations store. Continuing...", ).yellow().to_string()))
-            }
-        }
+    if let Some(ipfs_hash) = local.keys().next() {
+        return compare_subgraph_attestations(attestation_block, ipfs_hash, &remote, &local);
     }
 
     Err(CompareError::NonCritical(
@@ -453,3 +1092,401 @@ pub async fn compare_attestations(
         )
     )
 }
+
+/// Compares our attestation for one subgraph on the given block to the top-attested remote one
+pub fn compare_subgraph_attestations(
+    attestation_block: u64,
+    ipfs_hash: &str,
+    remote: &RemoteAttestationsMap,
+    local: &LocalAttestationsMap,
+) -> Result<String, CompareError> {
+    let local_attestation = match local
+        .get(ipfs_hash)
+        .and_then(|blocks| blocks.get(&attestation_block))
+    {
+        Some(local_attestation) => local_attestation,
+        None => {
+            return Err(CompareError::NonCritical(format!("No attestation for subgraph {ipfs_hash} on block {attestation_block} found in local attestations store. Continuing...", ).yellow().to_string()))
+        }
+    };
+
+    let remote_blocks = match remote.get(ipfs_hash) {
+        Some(remote_blocks) => remote_blocks,
+        None => {
+            return Err(CompareError::NonCritical(format!("No attestations for subgraph {ipfs_hash} on block {attestation_block} found in remote attestations store. Continuing...", ).yellow().to_string()))
+        }
+    };
+
+    match remote_blocks
+        .get(&attestation_block)
+        .and_then(|remote_attestations| most_attested_npoi(remote_attestations))
+    {
+        Some(most_attested_npoi) if most_attested_npoi == &local_attestation.npoi => Ok(format!(
+            "POIs match for subgraph {ipfs_hash} on block {attestation_block}!"
+        )),
+        Some(_) => Err(CompareError::Critical(
+            format!("POIs don't match for subgraph {ipfs_hash} on block {attestation_block}!")
+                .red()
+                .bold()
+                .to_string(),
+        )),
+        None => Err(CompareError::NonCritical(
+            format!(
+                "No record for subgraph {ipfs_hash} on block {attestation_block} found in remote attestations"
+            )
+            .yellow()
+            .to_string(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::signers::{LocalWallet, Signer};
+
+    const SUBGRAPH: &str = "QmggQnSgia4iDPWHpeY6aWxesRFdb8o5DKZUx96zZqEWrB";
+    const DUMMY_VALUE: i32 = 5;
+    const GRAPH_NODE_VERSION: &str = "0.30.0";
+    const POI_ALGORITHM: &str = "legacy";
+    const CUSTOM_NETWORK: &str = "test-chain";
+
+    /// Well-known development key (first Hardhat account), never use it for anything real
+    const SIGNER_KEY: &str = "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+    const SIGNER_ADDRESS: &str = "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266";
+    const POI: &str = "0x25331f98b82ca7f3966256bf508a7ede52e715b631dfa3d73b846bb7617f6b9e";
+    const BLOCK_HASH: &str = "4dbba1ba9fb18b0034965712598be1368edcf91ae2c551d59462aab578dab9c5";
+
+    const RADIO_DOMAIN_SEPARATOR: &str =
+        "79d383fcebe01bf106a3ccce11d32c9bd27fc6a783011ccfa3d4d6ef5b3c4ec0";
+    const RADIO_TYPE_HASH: &str =
+        "3f70836f827200db03f5a7d29d802dd24fc6d53116fdfeb085efebca944b21ef";
+    const RADIO_STRUCT_HASH: &str =
+        "a05c4d9d6ab77f021359a144cc1c84c8bb4a2a80815d7e729414f767cd7ef3eb";
+    const RADIO_EIP712_HASH: &str =
+        "8308aa88323e7d1af8ec21871458668477cec57529499acd55c3d034f6ab19ca";
+    const RADIO_SIGNATURE: &str = "ed763e875a11502b10a5d4fa085af7dffcbc165d8973f6e54104bd583d0a50f9177bd207362f2f2e5a5eb663a2a353a7ec53150e6d17989ae7f931ffdec984371c";
+    const RADIO_ENCODED: &str = "0a2e516d6767516e5367696134694450574870655936615778657352466462386f35444b5a557839367a5a71455772421242307832353333316639386238326361376633393636323536626635303861376564653532653731356236333164666133643733623834366262373631376636623965";
+
+    const RADIO_V2_DOMAIN_SEPARATOR: &str =
+        "25f459d02d138a481d513e9fd8224b3df9193982896b449750913f580d622fcd";
+    const RADIO_V2_TYPE_HASH: &str =
+        "19073e1dfaed681f46e75eea792c52ee8e0b133fc82eb4e00301a56200d5ede1";
+    const RADIO_V2_STRUCT_HASH: &str =
+        "a4193670a48677a0d4dfea455264a1bdf63b616e0502f87b965b7d02548ac9de";
+    const RADIO_V2_EIP712_HASH: &str =
+        "e583171828ad361c0bb1879c3b112b3680e79b7d2960d10b1b3d19c6fb984a48";
+    const RADIO_V2_SIGNATURE: &str = "aa3a637692c1a525ed1a7c5f2ec0940e7b99ff1d832cb2172472cc5a78355c00664c6fcbe28218e6f096390c1c3122a07843cdf5d5b5ab0d9ea87583627ecf5f1b";
+    const RADIO_V2_ENCODED: &str = "0a2e516d6767516e5367696134694450574870655936615778657352466462386f35444b5a557839367a5a7145577242124230783235333331663938623832636137663339363632353662663530386137656465353265373135623633316466613364373362383436626237363137663662396518022240346462626131626139666231386230303334393635373132353938626531333638656463663931616532633535316435393436326161623537386461623963352a06302e33302e3032066c6567616379";
+
+    const DUMMY_DOMAIN_SEPARATOR: &str =
+        "4e9761c3f61891d2d03a3472e3de68c90cf5f78c9e4d9362e64cf0520ce65608";
+    const DUMMY_TYPE_HASH: &str =
+        "ae1e237f550f9f0b783cc0b065b2f108d0954848ad5fe63ca751a17e2f2a08ae";
+    const DUMMY_STRUCT_HASH: &str =
+        "934442cf56622d1b5cc2681ab096f758ba222b7b4635206fbca1ef225131ffba";
+    const DUMMY_EIP712_HASH: &str =
+        "3c8368899bc397f4caeda479fedfc5abdb00980748d3029f44ebbde69985a792";
+    const DUMMY_SIGNATURE: &str = "19a58c9a0bcac40dc1abe7a54711ed397466f90c66ef9a8fe6ea51163d3abff730ebf857828890970af4b9de95cb6dad997812ee2b6242d6e86e04c2894494131b";
+    const DUMMY_ENCODED: &str = "0a2e516d6767516e5367696134694450574870655936615778657352466462386f35444b5a557839367a5a71455772421005";
+
+    /// Wraps a payload the same way the SDK does before it goes out, so we can recover the signer from it
+    fn signed_message<T>(payload: T, signature: &str) -> GraphcastMessage<T>
+    where
+        T: Message + Eip712 + Default + Clone + 'static,
+    {
+        GraphcastMessage {
+            identifier: SUBGRAPH.to_string(),
+            payload: Some(payload),
+            nonce: 0,
+            network: "mainnet".to_string(),
+            block_number: 0,
+            block_hash: String::new(),
+            signature: signature.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn radio_payload_vectors() {
+        let wallet = SIGNER_KEY.parse::<LocalWallet>().unwrap();
+        let payload = RadioPayloadMessage::new(SUBGRAPH.to_string(), POI.to_string());
+
+        assert_eq!(
+            hex::encode(payload.domain_separator().unwrap()),
+            RADIO_DOMAIN_SEPARATOR
+        );
+        assert_eq!(
+            hex::encode(RadioPayloadMessage::type_hash().unwrap()),
+            RADIO_TYPE_HASH
+        );
+        assert_eq!(
+            hex::encode(payload.struct_hash().unwrap()),
+            RADIO_STRUCT_HASH
+        );
+        assert_eq!(
+            hex::encode(payload.encode_eip712().unwrap()),
+            RADIO_EIP712_HASH
+        );
+
+        let signature = wallet.sign_typed_data(&payload).await.unwrap();
+        assert_eq!(signature.to_string(), RADIO_SIGNATURE);
+
+        let sender = signed_message(payload.clone(), RADIO_SIGNATURE)
+            .recover_sender_address()
+            .unwrap();
+        assert!(sender.eq_ignore_ascii_case(SIGNER_ADDRESS));
+
+        let encoded = payload.encode_to_vec();
+        assert_eq!(hex::encode(&encoded), RADIO_ENCODED);
+        let decoded = RadioPayloadMessage::decode(encoded.as_slice()).unwrap();
+        assert_eq!(decoded.identifier, payload.identifier);
+        assert_eq!(decoded.content, payload.content);
+
+        // Receivers decode into InboundPayload, which has to land on the same hash and signer
+        let inbound = InboundPayload::decode(encoded.as_slice()).unwrap();
+        assert!(matches!(inbound, InboundPayload::Radio(_)));
+        assert_eq!(
+            hex::encode(inbound.variant_type_hash().unwrap()),
+            RADIO_TYPE_HASH
+        );
+        assert_eq!(
+            hex::encode(inbound.encode_eip712().unwrap()),
+            RADIO_EIP712_HASH
+        );
+        let sender = signed_message(inbound, RADIO_SIGNATURE)
+            .recover_sender_address()
+            .unwrap();
+        assert!(sender.eq_ignore_ascii_case(SIGNER_ADDRESS));
+    }
+
+    #[tokio::test]
+    async fn radio_payload_v2_vectors() {
+        let wallet = SIGNER_KEY.parse::<LocalWallet>().unwrap();
+        let payload = RadioPayloadMessageV2::new(
+            SUBGRAPH.to_string(),
+            POI.to_string(),
+            BLOCK_HASH.to_string(),
+            GRAPH_NODE_VERSION.to_string(),
+            POI_ALGORITHM.to_string(),
+        );
+
+        assert_eq!(
+            hex::encode(payload.domain_separator().unwrap()),
+            RADIO_V2_DOMAIN_SEPARATOR
+        );
+        assert_eq!(
+            hex::encode(RadioPayloadMessageV2::type_hash().unwrap()),
+            RADIO_V2_TYPE_HASH
+        );
+        assert_eq!(
+            hex::encode(payload.struct_hash().unwrap()),
+            RADIO_V2_STRUCT_HASH
+        );
+        assert_eq!(
+            hex::encode(payload.encode_eip712().unwrap()),
+            RADIO_V2_EIP712_HASH
+        );
+
+        let signature = wallet.sign_typed_data(&payload).await.unwrap();
+        assert_eq!(signature.to_string(), RADIO_V2_SIGNATURE);
+
+        let encoded = payload.encode_to_vec();
+        assert_eq!(hex::encode(&encoded), RADIO_V2_ENCODED);
+
+        // A v1 decoder skips the extra fields and still reads the same NPOI
+        let decoded = RadioPayloadMessage::decode(encoded.as_slice()).unwrap();
+        assert_eq!(decoded.identifier, payload.identifier);
+        assert_eq!(decoded.content, payload.content);
+
+        let inbound = InboundPayload::decode(encoded.as_slice()).unwrap();
+        assert_eq!(inbound.version(), Some(RadioPayloadMessageV2::VERSION));
+        assert_eq!(
+            hex::encode(inbound.variant_type_hash().unwrap()),
+            RADIO_V2_TYPE_HASH
+        );
+        match &inbound {
+            InboundPayload::RadioV2(decoded) => {
+                assert_eq!(decoded.block_hash, payload.block_hash);
+                assert_eq!(decoded.graph_node_version, payload.graph_node_version);
+                assert_eq!(decoded.poi_algorithm, payload.poi_algorithm);
+            }
+            _ => panic!("v2 payload was not decoded as v2"),
+        }
+        let sender = signed_message(inbound, RADIO_V2_SIGNATURE)
+            .recover_sender_address()
+            .unwrap();
+        assert!(sender.eq_ignore_ascii_case(SIGNER_ADDRESS));
+    }
+
+    #[test]
+    fn custom_networks_are_named_in_the_payload() {
+        let custom = NetworkName::from_string("test-chain");
+        assert_eq!(
+            custom.to_wire(),
+            (SdkNetworkName::Unknown, Some("test-chain".to_string()))
+        );
+        assert_eq!(
+            NetworkName::Mainnet.to_wire(),
+            (SdkNetworkName::Mainnet, None)
+        );
+
+        let payload = RadioPayloadMessageV2::new(
+            SUBGRAPH.to_string(),
+            POI.to_string(),
+            BLOCK_HASH.to_string(),
+            GRAPH_NODE_VERSION.to_string(),
+            POI_ALGORITHM.to_string(),
+        )
+        .with_network(custom.to_string());
+        let inbound = InboundPayload::decode(payload.encode_to_vec().as_slice()).unwrap();
+        let received = |network: &str| GraphcastMessage {
+            identifier: SUBGRAPH.to_string(),
+            payload: Some(inbound.clone()),
+            nonce: 0,
+            network: network.to_string(),
+            block_number: 0,
+            block_hash: String::new(),
+            signature: String::new(),
+        };
+
+        let restored = InboundPayload::into_radio_message(received("unknown")).unwrap();
+        assert_eq!(NetworkName::from_string(&restored.network), custom);
+        assert_eq!(restored.payload.unwrap().content, POI);
+
+        // A chain the SDK did name on the wire isn't overridden by the payload
+        let named = InboundPayload::into_radio_message(received("mainnet")).unwrap();
+        assert_eq!(named.network, "mainnet");
+    }
+
+    #[tokio::test]
+    async fn dummy_payload_vectors() {
+        let wallet = SIGNER_KEY.parse::<LocalWallet>().unwrap();
+        let payload = DummyMsg::new(SUBGRAPH.to_string(), DUMMY_VALUE);
+
+        assert_eq!(
+            hex::encode(payload.domain_separator().unwrap()),
+            DUMMY_DOMAIN_SEPARATOR
+        );
+        assert_eq!(hex::encode(DummyMsg::type_hash().unwrap()), DUMMY_TYPE_HASH);
+        assert_eq!(
+            hex::encode(payload.struct_hash().unwrap()),
+            DUMMY_STRUCT_HASH
+        );
+        assert_eq!(
+            hex::encode(payload.encode_eip712().unwrap()),
+            DUMMY_EIP712_HASH
+        );
+
+        let signature = wallet.sign_typed_data(&payload).await.unwrap();
+        assert_eq!(signature.to_string(), DUMMY_SIGNATURE);
+
+        let sender = signed_message(payload.clone(), DUMMY_SIGNATURE)
+            .recover_sender_address()
+            .unwrap();
+        assert!(sender.eq_ignore_ascii_case(SIGNER_ADDRESS));
+
+        let encoded = payload.encode_to_vec();
+        assert_eq!(hex::encode(&encoded), DUMMY_ENCODED);
+        let decoded = DummyMsg::decode(encoded.as_slice()).unwrap();
+        assert_eq!(decoded.identifier, payload.identifier);
+        assert_eq!(decoded.dummy_value, payload.dummy_value);
+
+        // DummyMsg bytes must never decode as a RadioPayloadMessage, only as the Dummy side of InboundPayload
+        assert!(RadioPayloadMessage::decode(encoded.as_slice()).is_err());
+        let inbound = InboundPayload::decode(encoded.as_slice()).unwrap();
+        match &inbound {
+            InboundPayload::Dummy(_, raw) => assert_eq!(raw, &encoded),
+            _ => panic!("DummyMsg was not decoded as a DummyMsg"),
+        }
+        assert_eq!(
+            hex::encode(inbound.variant_type_hash().unwrap()),
+            DUMMY_TYPE_HASH
+        );
+        let sender = signed_message(inbound, DUMMY_SIGNATURE)
+            .recover_sender_address()
+            .unwrap();
+        assert!(sender.eq_ignore_ascii_case(SIGNER_ADDRESS));
+
+        // A zero dummy_value is left off the wire, which leaves nothing to tell the bytes from a radio payload without
+        // an NPOI, and those never carry an attestation
+        let zero = DummyMsg::new(SUBGRAPH.to_string(), 0);
+        let encoded = zero.encode_to_vec();
+        let inbound = InboundPayload::decode(encoded.as_slice()).unwrap();
+        match &inbound {
+            InboundPayload::Dummy(decoded, raw) => {
+                assert_eq!(decoded.identifier, zero.identifier);
+                assert_eq!(decoded.dummy_value, 0);
+                assert_eq!(raw, &encoded);
+            }
+            _ => panic!("A zero-valued DummyMsg was not decoded as a DummyMsg"),
+        }
+    }
+
+    /// Every variant the SDK knows about, kept in sync with `From<SdkNetworkName> for NetworkName`
+    const SDK_NETWORKS: [SdkNetworkName; 11] = [
+        SdkNetworkName::Goerli,
+        SdkNetworkName::Mainnet,
+        SdkNetworkName::Gnosis,
+        SdkNetworkName::Hardhat,
+        SdkNetworkName::ArbitrumOne,
+        SdkNetworkName::ArbitrumGoerli,
+        SdkNetworkName::Avalanche,
+        SdkNetworkName::Polygon,
+        SdkNetworkName::Celo,
+        SdkNetworkName::Optimism,
+        SdkNetworkName::Unknown,
+    ];
+
+    #[test]
+    fn built_in_network_names_round_trip() {
+        for network in NetworkName::BUILT_IN {
+            assert_eq!(
+                NetworkName::from_string(&network.to_string()),
+                network,
+                "{network} does not round trip through its name"
+            );
+
+            let sdk_network = SdkNetworkName::try_from(&network)
+                .unwrap_or_else(|e| panic!("{network} could not be converted: {e}"));
+            assert_eq!(
+                NetworkName::from(sdk_network),
+                network,
+                "{network} does not round trip through the SDK"
+            );
+        }
+    }
+
+    #[test]
+    fn sdk_network_names_round_trip() {
+        for sdk_network in SDK_NETWORKS {
+            let network = NetworkName::from(sdk_network);
+            assert_eq!(SdkNetworkName::try_from(&network).unwrap(), sdk_network);
+            assert_eq!(
+                network.to_string(),
+                sdk_network.to_string(),
+                "{network} is named differently by the SDK"
+            );
+        }
+    }
+
+    #[test]
+    fn custom_network_names_round_trip() {
+        let custom = NetworkName::from_string(CUSTOM_NETWORK);
+        assert_eq!(custom, NetworkName::Custom(CUSTOM_NETWORK.to_string()));
+        assert_eq!(NetworkName::from_string(&custom.to_string()), custom);
+        assert!(
+            SdkNetworkName::try_from(&custom).is_err(),
+            "Custom network was silently converted to an SDK network"
+        );
+    }
+
+    #[test]
+    fn registered_network_names_round_trip() {
+        for network in NETWORKS.iter() {
+            assert_eq!(
+                NetworkName::from_string(&network.name.to_string()),
+                network.name
+            );
+        }
+    }
+}
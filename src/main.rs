@@ -1,29 +1,55 @@
 pub mod checks;
+mod commands;
 mod graphql;
 pub mod setup;
 
 use checks::test_poi_ok::run_poi_ok;
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use commands::{
+    compose::run_compose,
+    orchestrate::{run_orchestrate, Orchestration},
+    report::run_report,
+};
 use graphcast_sdk::init_tracing;
-use setup::basic::run_basic_instance;
-use std::str::FromStr;
+use poi_radio_e2e_tests::{reports::ReportFormat, validate_networks_config};
+use setup::{
+    basic::run_basic_instance,
+    boot::run_boot_instance,
+    load::{run_load_instance, run_publisher_instance, LoadConfig},
+};
+use std::{path::PathBuf, str::FromStr, time::Duration};
 use tracing::{error, info};
 
 use crate::{
     checks::{
+        attestation_report::run_attestation_report, batched_poi::run_batched_poi,
+        block_alignment::run_block_alignment,
         correct_filtering_default_topics::run_correct_filtering_default_topics,
         correct_filtering_different_topics::run_correct_filtering_different_topics,
+        custom_network::run_custom_network, delivery_latency::run_delivery_latency,
+        divergence_notifiers::run_divergence_notifiers,
+        dynamic_allocations::run_dynamic_allocations, graph_node_faults::run_graph_node_faults,
         invalid_block_hash::run_invalid_block_hash, invalid_payload::run_invalid_payload,
         invalid_sender::run_invalid_sender, invalid_time::run_invalid_time,
-        skip_messages_from_self::run_skip_messages_from_self, test_num_messages::run_num_messages,
+        lagging_deployment::run_lagging_deployment, local_block_hash::run_local_block_hash,
+        mixed_versions::run_mixed_versions, multi_chain::run_multi_chain,
+        operator_rotation::run_operator_rotation, poi_divergence::run_poi_divergence,
+        skip_messages_from_self::run_skip_messages_from_self, skip_unhealthy::run_skip_unhealthy,
+        stake_threshold::run_stake_above_minimum, stake_threshold::run_stake_at_minimum,
+        stake_threshold::run_stake_below_minimum, stake_threshold::run_stake_threshold_change,
+        test_num_messages::run_num_messages,
     },
-    setup::invalid_payload::run_invalid_payload_instance,
+    setup::{display::run_display_instance, invalid_payload::run_invalid_payload_instance},
 };
 
 #[derive(Clone, Debug)]
 enum Instance {
     Basic,
     InvalidPayload,
+    Display,
+    Boot,
+    Load,
+    Publisher,
 }
 
 #[derive(Clone, Debug)]
@@ -37,6 +63,25 @@ enum Check {
     InvalidBlockHash,
     InvalidPayload,
     SkipMessagesFromSelf,
+    MixedVersions,
+    CustomNetwork,
+    BlockAlignment,
+    BatchedPoi,
+    LocalBlockHash,
+    SkipUnhealthy,
+    GraphNodeFaults,
+    MultiChain,
+    LaggingDeployment,
+    PoiDivergence,
+    DivergenceNotifiers,
+    AttestationReport,
+    DeliveryLatency,
+    DynamicAllocations,
+    OperatorRotation,
+    StakeBelowMinimum,
+    StakeAtMinimum,
+    StakeAboveMinimum,
+    StakeThresholdChange,
 }
 
 /// Simple program to greet a person
@@ -49,6 +94,69 @@ struct Args {
     check: Option<String>,
     #[arg(long)]
     count: Option<u32>,
+    #[arg(long)]
+    payload_version: Option<u32>,
+    /// Bound on the p95 delivery latency for the delivery_latency check
+    #[arg(long)]
+    max_latency_ms: Option<u64>,
+    /// Simulated senders of the load instance
+    #[arg(long)]
+    senders: Option<u32>,
+    /// Messages each simulated sender or publisher broadcasts per second
+    #[arg(long)]
+    rate: Option<f64>,
+    /// Subgraph deployments the load is spread across
+    #[arg(long)]
+    topics: Option<u32>,
+    /// Subgraph deployments a publisher attests to, can be given more than once
+    #[arg(long)]
+    subgraph: Vec<String>,
+    /// Network all of a publisher's subgraphs index, instead of the default ones
+    #[arg(long)]
+    network: Option<String>,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Merge the JSON reports written at the end of runs (RADIO_REPORT_PATH) into one CSV or JSON report
+    Report {
+        #[arg(required = true)]
+        inputs: Vec<PathBuf>,
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Defaults to CSV for a .csv output and JSON otherwise
+        #[arg(long)]
+        format: Option<ReportFormat>,
+    },
+    /// Run a check against a boot node and setup instances, each in its own process, and exit with its verdict
+    Orchestrate {
+        check: String,
+        /// The kind of setup instance to start
+        #[arg(long, default_value = "basic")]
+        instance: String,
+        /// Number of setup instances
+        #[arg(long, default_value_t = 3)]
+        instances: u32,
+        #[arg(long, default_value_t = 60000)]
+        boot_port: u16,
+        /// Seconds each node gets to start listening
+        #[arg(long, default_value_t = 60)]
+        ready_timeout: u64,
+        /// Seconds the check gets to reach a verdict
+        #[arg(long, default_value_t = 600)]
+        timeout: u64,
+    },
+    /// Generate a docker-compose file from a JSON scenario: the setup instances with their overrides and the check
+    Compose {
+        scenario: PathBuf,
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Also bring the services up and exit with the check's verdict
+        #[arg(long)]
+        up: bool,
+    },
 }
 
 impl FromStr for Instance {
@@ -58,6 +166,10 @@ impl FromStr for Instance {
         match s {
             "basic" => Ok(Instance::Basic),
             "invalid_payload" => Ok(Instance::InvalidPayload),
+            "display" => Ok(Instance::Display),
+            "boot" => Ok(Instance::Boot),
+            "load" => Ok(Instance::Load),
+            "publisher" => Ok(Instance::Publisher),
             _ => Err(format!("Invalid instance type: {s}")),
         }
     }
@@ -77,6 +189,25 @@ impl FromStr for Check {
             "invalid_hash" => Ok(Check::InvalidBlockHash),
             "invalid_payload" => Ok(Check::InvalidPayload),
             "skip_messages_from_self" => Ok(Check::SkipMessagesFromSelf),
+            "mixed_versions" => Ok(Check::MixedVersions),
+            "custom_network" => Ok(Check::CustomNetwork),
+            "block_alignment" => Ok(Check::BlockAlignment),
+            "batched_poi" => Ok(Check::BatchedPoi),
+            "local_block_hash" => Ok(Check::LocalBlockHash),
+            "skip_unhealthy" => Ok(Check::SkipUnhealthy),
+            "graph_node_faults" => Ok(Check::GraphNodeFaults),
+            "multi_chain" => Ok(Check::MultiChain),
+            "lagging_deployment" => Ok(Check::LaggingDeployment),
+            "poi_divergence" => Ok(Check::PoiDivergence),
+            "divergence_notifiers" => Ok(Check::DivergenceNotifiers),
+            "attestation_report" => Ok(Check::AttestationReport),
+            "delivery_latency" => Ok(Check::DeliveryLatency),
+            "dynamic_allocations" => Ok(Check::DynamicAllocations),
+            "operator_rotation" => Ok(Check::OperatorRotation),
+            "stake_below_minimum" => Ok(Check::StakeBelowMinimum),
+            "stake_at_minimum" => Ok(Check::StakeAtMinimum),
+            "stake_above_minimum" => Ok(Check::StakeAboveMinimum),
+            "stake_threshold_change" => Ok(Check::StakeThresholdChange),
             _ => Err(format!("Invalid check type: {s}")),
         }
     }
@@ -84,16 +215,86 @@ impl FromStr for Check {
 
 #[tokio::main]
 pub async fn main() {
-    init_tracing().expect("Could not set up global default subscriber");
     let args = Args::parse();
+    // The display redraws stdout in place, logs interleaved with it would be wiped or break up the table
+    if args.instance.as_deref() == Some("display") {
+        tracing_subscriber::fmt()
+            .with_writer(std::io::stderr)
+            .init();
+    } else {
+        init_tracing().expect("Could not set up global default subscriber");
+    }
+
+    if let Err(err) = validate_networks_config() {
+        error!("Invalid RADIO_NETWORKS configuration: {}", err);
+        std::process::exit(1);
+    }
+
+    match &args.command {
+        Some(Command::Report {
+            inputs,
+            output,
+            format,
+        }) => {
+            if let Err(err) = run_report(inputs, output, *format) {
+                error!("Could not write the report: {}", err);
+                std::process::exit(1);
+            }
+        }
+        Some(Command::Orchestrate {
+            check,
+            instance,
+            instances,
+            boot_port,
+            ready_timeout,
+            timeout,
+        }) => {
+            if let Err(err) = Check::from_str(check).and(Instance::from_str(instance)) {
+                error!("Error: {}", err);
+                std::process::exit(1);
+            }
+
+            let orchestration = Orchestration {
+                check: check.clone(),
+                instance: instance.clone(),
+                instances: *instances,
+                boot_port: *boot_port,
+                ready_timeout: Duration::from_secs(*ready_timeout),
+                timeout: Duration::from_secs(*timeout),
+            };
+            match run_orchestrate(&orchestration).await {
+                Ok(code) => {
+                    info!("{} exited with {}", check, code);
+                    std::process::exit(code);
+                }
+                Err(err) => {
+                    error!("Orchestration failed: {}", err);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Some(Command::Compose {
+            scenario,
+            output,
+            up,
+        }) => match run_compose(scenario, output, *up) {
+            Ok(code) => std::process::exit(code),
+            Err(err) => {
+                error!("Could not compose the scenario: {}", err);
+                std::process::exit(1);
+            }
+        },
+        None => {}
+    }
 
     if let Some(instance) = &args.instance {
         match Instance::from_str(instance) {
             Ok(Instance::Basic) => {
                 info!("Starting basic instance");
+                let payload_version = args.payload_version.unwrap_or(1);
 
-                std::thread::spawn(|| {
-                    run_basic_instance();
+                std::thread::spawn(move || {
+                    run_basic_instance(payload_version);
                 })
                 .join()
                 .expect("Thread panicked")
@@ -107,6 +308,50 @@ pub async fn main() {
                 .join()
                 .expect("Thread panicked")
             }
+            Ok(Instance::Display) => {
+                info!("Starting display instance");
+
+                std::thread::spawn(|| {
+                    run_display_instance();
+                })
+                .join()
+                .expect("Thread panicked")
+            }
+            Ok(Instance::Boot) => {
+                info!("Starting boot instance");
+
+                std::thread::spawn(|| {
+                    run_boot_instance();
+                })
+                .join()
+                .expect("Thread panicked")
+            }
+            Ok(Instance::Load) => {
+                let load = LoadConfig {
+                    senders: args.senders.unwrap_or(10),
+                    rate: args.rate.unwrap_or(1.0),
+                    topics: args.topics.unwrap_or(1),
+                };
+                info!("Starting load instance with {:?}", load);
+
+                std::thread::spawn(move || {
+                    run_load_instance(load);
+                })
+                .join()
+                .expect("Thread panicked")
+            }
+            Ok(Instance::Publisher) => {
+                let rate = args.rate.unwrap_or(1.0);
+                let subgraphs = args.subgraph.clone();
+                let network = args.network.clone();
+                info!("Starting publisher instance attesting to {:?}", subgraphs);
+
+                std::thread::spawn(move || {
+                    run_publisher_instance(rate, subgraphs, network);
+                })
+                .join()
+                .expect("Thread panicked")
+            }
             Err(err) => error!("Error: {}", err),
         }
     }
@@ -176,6 +421,127 @@ pub async fn main() {
             })
             .join()
             .expect("Thread panicked"),
+            Ok(Check::MixedVersions) => std::thread::spawn(|| {
+                info!("Starting mixed_versions check");
+                run_mixed_versions();
+            })
+            .join()
+            .expect("Thread panicked"),
+            Ok(Check::CustomNetwork) => std::thread::spawn(|| {
+                info!("Starting custom_network check");
+                run_custom_network();
+            })
+            .join()
+            .expect("Thread panicked"),
+            Ok(Check::BlockAlignment) => std::thread::spawn(|| {
+                info!("Starting block_alignment check");
+                run_block_alignment();
+            })
+            .join()
+            .expect("Thread panicked"),
+            Ok(Check::BatchedPoi) => std::thread::spawn(|| {
+                info!("Starting batched_poi check");
+                run_batched_poi();
+            })
+            .join()
+            .expect("Thread panicked"),
+            Ok(Check::LocalBlockHash) => std::thread::spawn(|| {
+                info!("Starting local_block_hash check");
+                run_local_block_hash();
+            })
+            .join()
+            .expect("Thread panicked"),
+            Ok(Check::SkipUnhealthy) => std::thread::spawn(|| {
+                info!("Starting skip_unhealthy check");
+                run_skip_unhealthy();
+            })
+            .join()
+            .expect("Thread panicked"),
+            Ok(Check::GraphNodeFaults) => std::thread::spawn(|| {
+                info!("Starting graph_node_faults check");
+                run_graph_node_faults();
+            })
+            .join()
+            .expect("Thread panicked"),
+            Ok(Check::MultiChain) => std::thread::spawn(|| {
+                info!("Starting multi_chain check");
+                run_multi_chain();
+            })
+            .join()
+            .expect("Thread panicked"),
+            Ok(Check::LaggingDeployment) => std::thread::spawn(|| {
+                info!("Starting lagging_deployment check");
+                run_lagging_deployment();
+            })
+            .join()
+            .expect("Thread panicked"),
+            Ok(Check::PoiDivergence) => std::thread::spawn(|| {
+                info!("Starting poi_divergence check");
+                run_poi_divergence();
+            })
+            .join()
+            .expect("Thread panicked"),
+            Ok(Check::DivergenceNotifiers) => std::thread::spawn(|| {
+                info!("Starting divergence_notifiers check");
+                run_divergence_notifiers();
+            })
+            .join()
+            .expect("Thread panicked"),
+            Ok(Check::AttestationReport) => std::thread::spawn(|| {
+                info!("Starting attestation_report check");
+                run_attestation_report();
+            })
+            .join()
+            .expect("Thread panicked"),
+            Ok(Check::DeliveryLatency) => {
+                let max_latency_ms = args.max_latency_ms.unwrap_or_else(|| {
+                    error!("No 'max-latency-ms' argument provided, defaulting to '5000'.");
+                    5000
+                });
+
+                std::thread::spawn(move || {
+                    info!("Starting delivery_latency check");
+                    run_delivery_latency(max_latency_ms);
+                })
+                .join()
+                .expect("Thread panicked")
+            }
+            Ok(Check::DynamicAllocations) => std::thread::spawn(|| {
+                info!("Starting dynamic_allocations check");
+                run_dynamic_allocations();
+            })
+            .join()
+            .expect("Thread panicked"),
+            Ok(Check::OperatorRotation) => std::thread::spawn(|| {
+                info!("Starting operator_rotation check");
+                run_operator_rotation();
+            })
+            .join()
+            .expect("Thread panicked"),
+            Ok(Check::StakeBelowMinimum) => std::thread::spawn(|| {
+                info!("Starting stake_below_minimum check");
+                run_stake_below_minimum();
+            })
+            .join()
+            .expect("Thread panicked"),
+            Ok(Check::StakeAtMinimum) => std::thread::spawn(|| {
+                info!("Starting stake_at_minimum check");
+                run_stake_at_minimum();
+            })
+            .join()
+            .expect("Thread panicked"),
+            Ok(Check::StakeAboveMinimum) => std::thread::spawn(|| {
+                info!("Starting stake_above_minimum check");
+                run_stake_above_minimum();
+            })
+            .join()
+            .expect("Thread panicked"),
+            Ok(Check::StakeThresholdChange) => std::thread::spawn(|| {
+                info!("Starting stake_threshold_change check");
+                run_stake_threshold_change();
+            })
+            .join()
+            .expect("Thread panicked"),
             Err(err) => error!("Error: {}", err),
         }
     }
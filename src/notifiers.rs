@@ -0,0 +1,302 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+use std::{
+    collections::HashMap, env, future::Future, path::PathBuf, pin::Pin, process::Stdio,
+    time::Duration,
+};
+use tokio::{fs::OpenOptions, io::AsyncWriteExt, process::Command, time::timeout};
+use tracing::{debug, error};
+
+use crate::{
+    most_attested_npoi, Divergence, LocalAttestationsMap, RemoteAttestationsMap, SubgraphStatus,
+    HTTP_CLIENT,
+};
+
+/// Matches the terminal color codes comparison errors are formatted with
+static ANSI_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"\x1b\[[0-9;]*m").unwrap());
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long a notification command gets to finish before it is killed
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Critical,
+    NonCritical,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct RemoteAttestation {
+    pub npoi: String,
+    pub stake_weight: String,
+    pub senders: Vec<String>,
+}
+
+/// What we and the other Indexers attested for one subgraph on the compared block
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct SubgraphComparison {
+    pub identifier: String,
+    pub network: Option<String>,
+    pub local_npoi: Option<String>,
+    pub most_attested_npoi: Option<String>,
+    pub remote_attestations: Vec<RemoteAttestation>,
+}
+
+/// A comparison outcome worth telling someone about, with everything that went into it
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Notification {
+    pub severity: Severity,
+    pub message: String,
+    pub block: u64,
+    pub subgraphs: Vec<SubgraphComparison>,
+    /// Where the diverged subgraphs departed from the consensus, for critical outcomes
+    pub divergences: Vec<Divergence>,
+}
+
+impl Notification {
+    pub fn new(
+        severity: Severity,
+        message: &str,
+        block: u64,
+        remote: &RemoteAttestationsMap,
+        local: &LocalAttestationsMap,
+        statuses: &HashMap<String, SubgraphStatus>,
+        divergences: Vec<Divergence>,
+    ) -> Self {
+        let mut identifiers = local.keys().chain(remote.keys()).collect::<Vec<_>>();
+        identifiers.sort();
+        identifiers.dedup();
+
+        let subgraphs = identifiers
+            .into_iter()
+            .map(|identifier| {
+                let attestations = remote
+                    .get(identifier)
+                    .and_then(|blocks| blocks.get(&block))
+                    .cloned()
+                    .unwrap_or_default();
+
+                SubgraphComparison {
+                    identifier: identifier.clone(),
+                    network: statuses
+                        .get(identifier)
+                        .and_then(|status| status.primary_chain())
+                        .map(|chain| chain.network.to_string()),
+                    local_npoi: local
+                        .get(identifier)
+                        .and_then(|blocks| blocks.get(&block))
+                        .map(|attestation| attestation.npoi.clone()),
+                    most_attested_npoi: most_attested_npoi(&attestations).cloned(),
+                    remote_attestations: attestations
+                        .into_iter()
+                        .map(|attestation| RemoteAttestation {
+                            npoi: attestation.npoi,
+                            stake_weight: attestation.stake_weight.to_string(),
+                            senders: attestation.senders,
+                        })
+                        .collect(),
+                }
+            })
+            .filter(|subgraph| {
+                subgraph.local_npoi.is_some() || !subgraph.remote_attestations.is_empty()
+            })
+            .collect();
+
+        Notification {
+            severity,
+            message: ANSI_REGEX.replace_all(message, "").to_string(),
+            block,
+            subgraphs,
+            divergences,
+        }
+    }
+}
+
+pub type NotifyFuture<'a> = Pin<Box<dyn Future<Output = Result<(), anyhow::Error>> + Send + 'a>>;
+
+/// Gets told about critical and non-critical comparison outcomes
+pub trait Notifier: Send + Sync {
+    fn name(&self) -> String;
+
+    fn notify<'a>(&'a self, notification: &'a Notification) -> NotifyFuture<'a>;
+}
+
+/// POSTs the notification as JSON through the shared HTTP client
+pub struct WebhookNotifier {
+    url: String,
+}
+
+impl WebhookNotifier {
+    pub fn new(url: &str) -> Self {
+        WebhookNotifier {
+            url: url.to_string(),
+        }
+    }
+}
+
+impl Notifier for WebhookNotifier {
+    fn name(&self) -> String {
+        format!("webhook {}", self.url)
+    }
+
+    fn notify<'a>(&'a self, notification: &'a Notification) -> NotifyFuture<'a> {
+        Box::pin(async move {
+            HTTP_CLIENT
+                .post(&self.url)
+                .timeout(WEBHOOK_TIMEOUT)
+                .json(notification)
+                .send()
+                .await?
+                .error_for_status()?;
+            Ok(())
+        })
+    }
+}
+
+/// Appends the notification to a file as a line of JSON
+pub struct FileNotifier {
+    path: PathBuf,
+}
+
+impl FileNotifier {
+    pub fn new(path: PathBuf) -> Self {
+        FileNotifier { path }
+    }
+}
+
+impl Notifier for FileNotifier {
+    fn name(&self) -> String {
+        format!("file {}", self.path.display())
+    }
+
+    fn notify<'a>(&'a self, notification: &'a Notification) -> NotifyFuture<'a> {
+        Box::pin(async move {
+            let mut line = serde_json::to_vec(notification)?;
+            line.push(b'\n');
+
+            let mut file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)
+                .await?;
+            file.write_all(&line).await?;
+            Ok(())
+        })
+    }
+}
+
+/// Runs a shell command with the notification as JSON on its stdin, killing it if it doesn't finish in time
+pub struct CommandNotifier {
+    command: String,
+}
+
+impl CommandNotifier {
+    pub fn new(command: &str) -> Self {
+        CommandNotifier {
+            command: command.to_string(),
+        }
+    }
+}
+
+impl Notifier for CommandNotifier {
+    fn name(&self) -> String {
+        format!("command `{}`", self.command)
+    }
+
+    fn notify<'a>(&'a self, notification: &'a Notification) -> NotifyFuture<'a> {
+        Box::pin(async move {
+            let mut child = Command::new("sh")
+                .arg("-c")
+                .arg(&self.command)
+                .stdin(Stdio::piped())
+                .spawn()?;
+
+            let mut stdin = child.stdin.take().unwrap();
+            let payload = serde_json::to_vec(notification)?;
+            let finished = timeout(COMMAND_TIMEOUT, async {
+                stdin.write_all(&payload).await?;
+                drop(stdin);
+                child.wait().await
+            })
+            .await;
+
+            match finished {
+                Ok(status) => {
+                    let status = status?;
+                    if !status.success() {
+                        anyhow::bail!("exited with {status}");
+                    }
+                    Ok(())
+                }
+                Err(_) => {
+                    child.kill().await?;
+                    anyhow::bail!(
+                        "did not finish within {}s and was killed",
+                        COMMAND_TIMEOUT.as_secs()
+                    )
+                }
+            }
+        })
+    }
+}
+
+/// The built-in notifiers, as they are set up from a runtime config or the environment
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NotifierConfig {
+    Webhook(String),
+    File(PathBuf),
+    Command(String),
+}
+
+impl NotifierConfig {
+    /// Reads `NOTIFY_WEBHOOK_URL`, `NOTIFY_FILE` and `NOTIFY_COMMAND`, each one that is set adds a notifier
+    pub fn from_env() -> Vec<Self> {
+        let var = |name: &str| env::var(name).ok().filter(|value| !value.is_empty());
+
+        [
+            var("NOTIFY_WEBHOOK_URL").map(NotifierConfig::Webhook),
+            var("NOTIFY_FILE").map(|path| NotifierConfig::File(PathBuf::from(path))),
+            var("NOTIFY_COMMAND").map(NotifierConfig::Command),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    pub fn build(&self) -> Box<dyn Notifier> {
+        match self {
+            NotifierConfig::Webhook(url) => Box::new(WebhookNotifier::new(url)),
+            NotifierConfig::File(path) => Box::new(FileNotifier::new(path.clone())),
+            NotifierConfig::Command(command) => Box::new(CommandNotifier::new(command)),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Notifiers {
+    notifiers: Vec<Box<dyn Notifier>>,
+}
+
+impl Notifiers {
+    pub fn new(configs: &[NotifierConfig]) -> Self {
+        Notifiers {
+            notifiers: configs.iter().map(NotifierConfig::build).collect(),
+        }
+    }
+
+    pub fn add(&mut self, notifier: Box<dyn Notifier>) {
+        self.notifiers.push(notifier);
+    }
+
+    /// Tells every notifier in turn, a failing notifier is logged and doesn't keep the others from being told
+    pub async fn notify(&self, notification: &Notification) {
+        for notifier in &self.notifiers {
+            match notifier.notify(notification).await {
+                Ok(()) => debug!("Notified {}", notifier.name()),
+                Err(e) => error!("Could not notify {}: {e}", notifier.name()),
+            }
+        }
+    }
+}
@@ -0,0 +1,327 @@
+use graphcast_sdk::graphql::QueryError;
+use once_cell::sync::{Lazy, OnceCell};
+use regex::Regex;
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex as SyncMutex},
+};
+use tracing::error;
+
+/// Matches an Ethereum address in an error message
+static ADDRESS_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"0x[0-9a-fA-F]{40}").unwrap());
+/// Matches an address the SDK names as the sender of the message, as opposed to e.g. the Indexer it resolves to
+static SENDER_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)\b(?:sender|signer|from)\b(?:\W+address)?\W+(0x[0-9a-fA-F]{40})").unwrap()
+});
+
+/// Sender key used for rejections where the sender could not be determined
+pub const UNKNOWN_SENDER: &str = "unknown";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum RejectionReason {
+    BadSignature,
+    UnregisteredSender,
+    StakeBelowMinimum,
+    StaleNonce,
+    BlockHashMismatch,
+    /// The SDK accepted the message, but our own graph node has a different hash for its block
+    LocalBlockHashMismatch,
+    DecodeFailure,
+    SelfMessage,
+    TypeConfusion,
+    Other,
+}
+
+impl RejectionReason {
+    /// Classifies a validation error returned by the SDK. Errors that carry their original type, anywhere in the
+    /// chain, are classified by it. The SDK raises the rest as plain `anyhow` messages, which only leaves their wording
+    pub fn classify_error(err: &anyhow::Error) -> Self {
+        for cause in err.chain() {
+            if cause.is::<prost::DecodeError>() {
+                return RejectionReason::DecodeFailure;
+            }
+            if cause.is::<ethers::types::SignatureError>() {
+                return RejectionReason::BadSignature;
+            }
+            // Senders are resolved through the registry, which comes back empty for one that isn't registered
+            if let Some(QueryError::EmptyResponseError(_)) = cause.downcast_ref::<QueryError>() {
+                return RejectionReason::UnregisteredSender;
+            }
+        }
+
+        RejectionReason::classify(&format!("{err:#}"))
+    }
+
+    /// Classifies an SDK error by its wording. Only phrases the SDK raises its untyped errors with count, the tests
+    /// below pin them against the SDK's messages
+    pub fn classify(err: &str) -> Self {
+        let err = err.to_lowercase();
+        let matches_any = |needles: &[&str]| needles.iter().any(|needle| err.contains(needle));
+
+        if matches_any(&["failed to decode protobuf message"]) {
+            RejectionReason::DecodeFailure
+        } else if matches_any(&[
+            "outside acceptable range",
+            "invalid nonce",
+            "nonce hasn't been added",
+        ]) {
+            RejectionReason::StaleNonce
+        } else if matches_any(&["differ from trusted provider"]) {
+            RejectionReason::BlockHashMismatch
+        } else if matches_any(&["stake is less than the minimum requirement"]) {
+            RejectionReason::StakeBelowMinimum
+        } else if matches_any(&["signature verification failed", "public key recovery error"]) {
+            RejectionReason::BadSignature
+        } else {
+            RejectionReason::Other
+        }
+    }
+}
+
+impl fmt::Display for RejectionReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            RejectionReason::BadSignature => "bad signature",
+            RejectionReason::UnregisteredSender => "unregistered sender",
+            RejectionReason::StakeBelowMinimum => "stake below minimum",
+            RejectionReason::StaleNonce => "stale nonce",
+            RejectionReason::BlockHashMismatch => "block hash mismatch",
+            RejectionReason::LocalBlockHashMismatch => "local block hash mismatch",
+            RejectionReason::DecodeFailure => "decode failure",
+            RejectionReason::SelfMessage => "self message",
+            RejectionReason::TypeConfusion => "type confusion",
+            RejectionReason::Other => "other",
+        };
+
+        write!(f, "{reason}")
+    }
+}
+
+/// A single message that was dropped by the handler, along with why. The raw payload bytes are only
+/// available when the message made it through the SDK's decoding, i.e. for rejections made by the radio itself
+#[derive(Clone, Debug)]
+pub struct Rejection {
+    pub sender: Option<String>,
+    pub reason: RejectionReason,
+    pub error: String,
+    pub payload: Option<Vec<u8>>,
+}
+
+impl Rejection {
+    pub fn new(sender: Option<String>, reason: RejectionReason, error: String) -> Self {
+        Rejection {
+            sender,
+            reason,
+            error,
+            payload: None,
+        }
+    }
+
+    pub fn with_payload(mut self, payload: Vec<u8>) -> Self {
+        self.payload = Some(payload);
+        self
+    }
+
+    /// Builds a rejection from an error surfaced by the SDK. The sender is the address the message names as such,
+    /// or the only address in it. Any other address could as well be the Indexer or a contract, so it's left unknown
+    pub fn from_error(err: &anyhow::Error) -> Self {
+        let error = format!("{err:#}");
+        let sender = SENDER_REGEX
+            .captures(&error)
+            .map(|captures| captures[1].to_string())
+            .or_else(|| {
+                let mut addresses = ADDRESS_REGEX
+                    .find_iter(&error)
+                    .map(|address| address.as_str().to_lowercase())
+                    .collect::<Vec<_>>();
+                addresses.sort();
+                addresses.dedup();
+                match addresses.as_slice() {
+                    [address] => Some(address.clone()),
+                    _ => None,
+                }
+            })
+            .map(|address| address.to_lowercase());
+
+        Rejection::new(sender, RejectionReason::classify_error(err), error)
+    }
+}
+
+/// Keeps track of every message the handler has seen and the ones it had to drop,
+/// so checks can tell "nothing arrived" apart from "everything was rejected"
+#[derive(Debug, Default)]
+pub struct RejectionLog {
+    pub observed: u64,
+    pub rejections: Vec<Rejection>,
+    pub per_sender: HashMap<String, HashMap<RejectionReason, u64>>,
+}
+
+impl RejectionLog {
+    /// Called for every message the handler is invoked with, valid or not
+    pub fn observe(&mut self) {
+        self.observed += 1;
+    }
+
+    pub fn reject(&mut self, rejection: Rejection) {
+        error!(
+            "Rejected message from {} ({}): {}",
+            rejection.sender.as_deref().unwrap_or(UNKNOWN_SENDER),
+            rejection.reason,
+            rejection.error
+        );
+
+        *self
+            .per_sender
+            .entry(
+                rejection
+                    .sender
+                    .clone()
+                    .unwrap_or_else(|| UNKNOWN_SENDER.to_string()),
+            )
+            .or_default()
+            .entry(rejection.reason)
+            .or_default() += 1;
+        self.rejections.push(rejection);
+    }
+
+    /// Number of rejected messages for the given reason, across all senders
+    pub fn count(&self, reason: RejectionReason) -> usize {
+        self.rejections
+            .iter()
+            .filter(|rejection| rejection.reason == reason)
+            .count()
+    }
+}
+
+pub type RejectionLogArc = Arc<SyncMutex<RejectionLog>>;
+
+/// A global static (singleton) instance of the rejection log, populated by the attestation handlers
+pub static REJECTIONS: OnceCell<RejectionLogArc> = OnceCell::new();
+
+/// Records a rejection in the global log, if the log has been set up
+pub fn record_rejection(rejection: Rejection) {
+    if let Some(rejections) = REJECTIONS.get() {
+        rejections.lock().unwrap().reject(rejection);
+    }
+}
+
+/// Counts a message as seen by the handler, if the log has been set up
+pub fn record_observed() {
+    if let Some(rejections) = REJECTIONS.get() {
+        rejections.lock().unwrap().observe();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    const SENDER: &str = "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266";
+
+    /// Messages graphcast-sdk raises when a message fails validation, copied from its `message_typing` and nonce
+    /// checks with the values filled in
+    const SDK_STAKE_ERROR: &str = "Sender stake is less than the minimum requirement, drop message";
+    const SDK_TIME_ERROR: &str =
+        "Message timestamp 3620 outside acceptable range 3600, drop message";
+    const SDK_HASH_ERROR: &str =
+        "Message hash (0xabc) differ from trusted provider response (0xdef), drop message";
+    const SDK_NONCE_ERROR: &str = "Invalid nonce for subgraph QmggQnSgia4iDPWHpeY6aWxesRFdb8o5DKZUx96zZqEWrB and address 0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266! Received nonce - 1 is smaller than currently saved one - 2, skipping message...";
+
+    #[test]
+    fn classifies_sdk_messages() {
+        for (error, reason) in [
+            (SDK_STAKE_ERROR, RejectionReason::StakeBelowMinimum),
+            (SDK_TIME_ERROR, RejectionReason::StaleNonce),
+            (SDK_HASH_ERROR, RejectionReason::BlockHashMismatch),
+            (SDK_NONCE_ERROR, RejectionReason::StaleNonce),
+        ] {
+            assert_eq!(RejectionReason::classify(error), reason, "{error}");
+            assert_eq!(
+                RejectionReason::classify_error(&anyhow!(error)),
+                reason,
+                "{error}"
+            );
+        }
+    }
+
+    #[test]
+    fn classifies_typed_errors() {
+        let decode = anyhow::Error::new(prost::DecodeError::new("invalid wire type"));
+        assert_eq!(
+            RejectionReason::classify_error(&decode),
+            RejectionReason::DecodeFailure
+        );
+
+        let signature = anyhow::Error::new(ethers::types::SignatureError::RecoveryError)
+            .context("Could not recover the sender");
+        assert_eq!(
+            RejectionReason::classify_error(&signature),
+            RejectionReason::BadSignature
+        );
+
+        let registry = anyhow::Error::new(QueryError::EmptyResponseError(SENDER.to_string()));
+        assert_eq!(
+            RejectionReason::classify_error(&registry),
+            RejectionReason::UnregisteredSender
+        );
+    }
+
+    #[test]
+    fn mentions_in_passing_are_not_classified() {
+        for error in [
+            "Could not fetch the stake of an Indexer",
+            "Block hash lookup failed",
+            "Timed out",
+        ] {
+            assert_eq!(RejectionReason::classify(error), RejectionReason::Other);
+        }
+    }
+
+    #[test]
+    fn counts_rejections_per_sender_and_reason() {
+        let mut log = RejectionLog::default();
+        log.observe();
+        log.observe();
+        log.observe();
+        log.reject(Rejection::new(
+            Some(SENDER.to_string()),
+            RejectionReason::StaleNonce,
+            SDK_NONCE_ERROR.to_string(),
+        ));
+        log.reject(Rejection::new(
+            Some(SENDER.to_string()),
+            RejectionReason::StaleNonce,
+            SDK_TIME_ERROR.to_string(),
+        ));
+        log.reject(Rejection::new(
+            None,
+            RejectionReason::Other,
+            "unknown".to_string(),
+        ));
+
+        assert_eq!(log.observed, 3);
+        assert_eq!(log.rejections.len(), 3);
+        assert_eq!(log.count(RejectionReason::StaleNonce), 2);
+        assert_eq!(log.count(RejectionReason::BadSignature), 0);
+        assert_eq!(log.per_sender[SENDER][&RejectionReason::StaleNonce], 2);
+        assert_eq!(log.per_sender[UNKNOWN_SENDER][&RejectionReason::Other], 1);
+    }
+
+    #[test]
+    fn picks_the_sender_address() {
+        let indexer = "0x2222222222222222222222222222222222222222";
+        let named = anyhow!("Indexer {indexer} resolved for sender {SENDER} has no stake");
+        assert_eq!(
+            Rejection::from_error(&named).sender.as_deref(),
+            Some(SENDER)
+        );
+
+        let only = anyhow!("Nonce hasn't been added for {SENDER}");
+        assert_eq!(Rejection::from_error(&only).sender.as_deref(), Some(SENDER));
+
+        let ambiguous = anyhow!("{indexer} and {SENDER}");
+        assert_eq!(Rejection::from_error(&ambiguous).sender, None);
+    }
+}
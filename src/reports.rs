@@ -0,0 +1,364 @@
+use anyhow::anyhow;
+use chrono::Utc;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt, fs,
+    path::Path,
+    str::FromStr,
+    sync::{Arc, Mutex as SyncMutex, TryLockError},
+};
+
+use crate::{
+    compare_subgraph_attestations, latency::LatencyLog, CompareError, LocalAttestationsMap,
+    RemoteAttestationsMap, SubgraphStatus,
+};
+
+const CSV_HEADER: &str = "run,kind,subgraph,block,network,npoi,stake_weight,senders,outcome,sender,samples,latency_p50_ms,latency_p95_ms,latency_p99_ms";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RowKind {
+    LocalAttestation,
+    RemoteAttestation,
+    Comparison,
+    /// Publish-to-receive latency percentiles of one sender, or of all of them when the row has no sender
+    DeliveryLatency,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ComparisonOutcome {
+    Match,
+    Diverged,
+    /// There was nothing to compare against, either locally or remotely
+    Inconclusive,
+}
+
+impl From<&Result<String, CompareError>> for ComparisonOutcome {
+    fn from(comparison: &Result<String, CompareError>) -> Self {
+        match comparison {
+            Ok(_) => ComparisonOutcome::Match,
+            Err(CompareError::Critical(_)) => ComparisonOutcome::Diverged,
+            Err(CompareError::NonCritical(_)) => ComparisonOutcome::Inconclusive,
+        }
+    }
+}
+
+impl fmt::Display for RowKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            RowKind::LocalAttestation => "local_attestation",
+            RowKind::RemoteAttestation => "remote_attestation",
+            RowKind::Comparison => "comparison",
+            RowKind::DeliveryLatency => "delivery_latency",
+        };
+
+        write!(f, "{kind}")
+    }
+}
+
+impl fmt::Display for ComparisonOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let outcome = match self {
+            ComparisonOutcome::Match => "match",
+            ComparisonOutcome::Diverged => "diverged",
+            ComparisonOutcome::Inconclusive => "inconclusive",
+        };
+
+        write!(f, "{outcome}")
+    }
+}
+
+/// One line of a report. Attestation rows carry the NPOI, remote ones also the stake behind it, comparison rows
+/// carry how our NPOI fared against the top-attested one and delivery latency rows carry percentiles in milliseconds.
+/// Latency fields default to empty so reports written before they existed can still be read
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReportRow {
+    pub run: String,
+    pub kind: RowKind,
+    pub subgraph: String,
+    pub block: u64,
+    pub network: String,
+    pub npoi: Option<String>,
+    pub stake_weight: Option<String>,
+    pub senders: Option<usize>,
+    pub outcome: Option<ComparisonOutcome>,
+    #[serde(default)]
+    pub sender: Option<String>,
+    #[serde(default)]
+    pub samples: Option<usize>,
+    #[serde(default)]
+    pub latency_p50_ms: Option<u64>,
+    #[serde(default)]
+    pub latency_p95_ms: Option<u64>,
+    #[serde(default)]
+    pub latency_p99_ms: Option<u64>,
+}
+
+impl ReportRow {
+    fn to_csv(&self) -> String {
+        let optional = |value: Option<String>| value.unwrap_or_default();
+
+        [
+            self.run.clone(),
+            self.kind.to_string(),
+            self.subgraph.clone(),
+            self.block.to_string(),
+            self.network.clone(),
+            optional(self.npoi.clone()),
+            optional(self.stake_weight.clone()),
+            optional(self.senders.map(|senders| senders.to_string())),
+            optional(self.outcome.map(|outcome| outcome.to_string())),
+            optional(self.sender.clone()),
+            optional(self.samples.map(|samples| samples.to_string())),
+            optional(self.latency_p50_ms.map(|latency| latency.to_string())),
+            optional(self.latency_p95_ms.map(|latency| latency.to_string())),
+            optional(self.latency_p99_ms.map(|latency| latency.to_string())),
+        ]
+        .iter()
+        .map(|field| csv_field(field))
+        .collect::<Vec<_>>()
+        .join(",")
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ReportFormat {
+    Csv,
+    Json,
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "csv" => Ok(ReportFormat::Csv),
+            "json" => Ok(ReportFormat::Json),
+            _ => Err(format!("Invalid report format: {s}")),
+        }
+    }
+}
+
+impl ReportFormat {
+    /// CSV for a `.csv` path, JSON for anything else
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some(extension) if extension.eq_ignore_ascii_case("csv") => ReportFormat::Csv,
+            _ => ReportFormat::Json,
+        }
+    }
+}
+
+pub fn write_report(
+    path: &Path,
+    format: ReportFormat,
+    rows: &[ReportRow],
+) -> Result<(), anyhow::Error> {
+    let contents = match format {
+        ReportFormat::Json => serde_json::to_string_pretty(rows)?,
+        ReportFormat::Csv => std::iter::once(CSV_HEADER.to_string())
+            .chain(rows.iter().map(ReportRow::to_csv))
+            .map(|line| line + "\n")
+            .collect(),
+    };
+
+    fs::write(path, contents)?;
+    Ok(())
+}
+
+/// Reads back a report written as JSON, CSV reports are meant for charting and aren't read back
+pub fn read_report(path: &Path) -> Result<Vec<ReportRow>, anyhow::Error> {
+    if ReportFormat::from_path(path) == ReportFormat::Csv {
+        return Err(anyhow!(
+            "{} is a CSV report, only JSON reports can be read back",
+            path.display()
+        ));
+    }
+
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+/// Everything this instance attested, received and compared over its run, as it stood after the latest comparison
+pub struct RunHistory {
+    run: String,
+    local: BTreeMap<(String, u64), ReportRow>,
+    remote: BTreeMap<(String, u64, String), ReportRow>,
+    comparisons: BTreeMap<(String, u64), ReportRow>,
+    latencies: BTreeMap<Option<String>, ReportRow>,
+}
+
+impl Default for RunHistory {
+    fn default() -> Self {
+        RunHistory {
+            run: format!(
+                "{}-{}",
+                Utc::now().format("%Y%m%dT%H%M%SZ"),
+                std::process::id()
+            ),
+            local: BTreeMap::new(),
+            remote: BTreeMap::new(),
+            comparisons: BTreeMap::new(),
+            latencies: BTreeMap::new(),
+        }
+    }
+}
+
+impl RunHistory {
+    fn row(&self, kind: RowKind, subgraph: &str, block: u64, network: String) -> ReportRow {
+        ReportRow {
+            run: self.run.clone(),
+            kind,
+            subgraph: subgraph.to_string(),
+            block,
+            network,
+            npoi: None,
+            stake_weight: None,
+            senders: None,
+            outcome: None,
+            sender: None,
+            samples: None,
+            latency_p50_ms: None,
+            latency_p95_ms: None,
+            latency_p99_ms: None,
+        }
+    }
+
+    /// Takes in the attestation stores and the comparison of `attestation_block` after a round of comparing.
+    /// Later rounds overwrite what earlier ones recorded for the same subgraph and block
+    pub fn record_round(
+        &mut self,
+        attestation_block: u64,
+        remote: &RemoteAttestationsMap,
+        local: &LocalAttestationsMap,
+        statuses: &HashMap<String, SubgraphStatus>,
+    ) {
+        let network = |subgraph: &str| {
+            statuses
+                .get(subgraph)
+                .and_then(|status| status.primary_chain())
+                .map(|chain| chain.network.to_string())
+                .unwrap_or_default()
+        };
+
+        for (subgraph, blocks) in local {
+            for (block, attestation) in blocks {
+                let mut row = self.row(
+                    RowKind::LocalAttestation,
+                    subgraph,
+                    *block,
+                    network(subgraph),
+                );
+                row.npoi = Some(attestation.npoi.clone());
+                self.local.insert((subgraph.clone(), *block), row);
+            }
+
+            let outcome = ComparisonOutcome::from(&compare_subgraph_attestations(
+                attestation_block,
+                subgraph,
+                remote,
+                local,
+            ));
+            let mut row = self.row(
+                RowKind::Comparison,
+                subgraph,
+                attestation_block,
+                network(subgraph),
+            );
+            row.outcome = Some(outcome);
+            self.comparisons
+                .insert((subgraph.clone(), attestation_block), row);
+        }
+
+        for (subgraph, blocks) in remote {
+            for (block, attestations) in blocks {
+                for attestation in attestations {
+                    let mut row = self.row(
+                        RowKind::RemoteAttestation,
+                        subgraph,
+                        *block,
+                        network(subgraph),
+                    );
+                    row.npoi = Some(attestation.npoi.clone());
+                    row.stake_weight = Some(attestation.stake_weight.to_string());
+                    row.senders = Some(attestation.senders.len());
+                    self.remote
+                        .insert((subgraph.clone(), *block, attestation.npoi.clone()), row);
+                }
+            }
+        }
+    }
+
+    /// Takes in the delivery latencies so far, replacing the percentiles recorded before
+    pub fn record_latencies(&mut self, log: &LatencyLog) {
+        let senders = log
+            .sender_percentiles()
+            .into_iter()
+            .map(|(sender, percentiles)| (Some(sender), percentiles));
+
+        self.latencies = log
+            .percentiles()
+            .map(|percentiles| (None, percentiles))
+            .into_iter()
+            .chain(senders)
+            .map(|(sender, percentiles)| {
+                let mut row = self.row(RowKind::DeliveryLatency, "", 0, String::new());
+                row.sender = sender.clone();
+                row.samples = Some(percentiles.samples);
+                row.latency_p50_ms = Some(percentiles.p50);
+                row.latency_p95_ms = Some(percentiles.p95);
+                row.latency_p99_ms = Some(percentiles.p99);
+                (sender, row)
+            })
+            .collect();
+    }
+
+    pub fn rows(&self) -> Vec<ReportRow> {
+        self.local
+            .values()
+            .chain(self.remote.values())
+            .chain(self.comparisons.values())
+            .chain(self.latencies.values())
+            .cloned()
+            .collect()
+    }
+}
+
+/// A global static (singleton) history of this instance's run, reported on when the run ends
+pub static RUN_HISTORY: OnceCell<Arc<SyncMutex<RunHistory>>> = OnceCell::new();
+
+/// Writes the history of this instance's run to `path`, in the format its extension asks for
+pub fn write_run_report(path: &Path) -> Result<(), anyhow::Error> {
+    let rows = RUN_HISTORY
+        .get()
+        .map(|history| history.lock().unwrap().rows())
+        .unwrap_or_default();
+
+    write_report(path, ReportFormat::from_path(path), &rows)
+}
+
+/// `write_run_report` for the panic hook. The hook runs before the panicking thread unwinds, so that thread may still
+/// hold the lock on the history and waiting for it would deadlock, nothing is written in that case. A lock poisoned
+/// by an earlier panic still holds everything recorded before it
+pub fn write_run_report_on_panic(path: &Path) -> Result<(), anyhow::Error> {
+    let rows = match RUN_HISTORY.get().map(|history| history.try_lock()) {
+        Some(Ok(history)) => history.rows(),
+        Some(Err(TryLockError::Poisoned(poisoned))) => poisoned.into_inner().rows(),
+        Some(Err(TryLockError::WouldBlock)) => {
+            anyhow::bail!("The run history is locked, possibly by the panicking thread")
+        }
+        None => Vec::new(),
+    };
+
+    write_report(path, ReportFormat::from_path(path), &rows)
+}
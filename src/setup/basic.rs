@@ -6,7 +6,8 @@ use poi_radio_e2e_tests::utils::RadioRuntimeConfig;
 fn success_handler(_messages: MessagesArc) {}
 
 #[tokio::main]
-pub async fn run_basic_instance() {
-    let config = RadioRuntimeConfig::default_config();
+pub async fn run_basic_instance(payload_version: u32) {
+    let mut config = RadioRuntimeConfig::default_config();
+    config.payload_version = payload_version;
     run_test_radio(&config, success_handler).await;
 }
@@ -0,0 +1,15 @@
+use poi_radio_e2e_tests::MessagesArc;
+
+use crate::setup::test_radio::run_test_radio;
+use poi_radio_e2e_tests::utils::RadioRuntimeConfig;
+
+fn success_handler(_messages: MessagesArc) {}
+
+/// The node the other instances boot from, it listens on `WAKU_PORT` with the `WAKU_NODE_KEY` identity and never
+/// sends attestations itself
+#[tokio::main]
+pub async fn run_boot_instance() {
+    let mut config = RadioRuntimeConfig::default_config();
+    config.observer = true;
+    run_test_radio(&config, success_handler).await;
+}
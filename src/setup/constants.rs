@@ -1,3 +1,15 @@
 pub static MOCK_SUBGRAPH_MAINNET: &str = "QmggQnSgia4iDPWHpeY6aWxesRFdb8o5DKZUx96zZqEWrB";
 pub static MOCK_SUBGRAPH_GOERLI: &str = "Qm11QnSgia4iDPWHpeY6aWxesRFdb8o5DKZUx96zZqEWrB";
 pub static MOCK_SUBGRAPH_GOERLI_2: &str = "Qm22QnSgia4iDPWHpeY6aWxesRFdb8o5DKZUx96zZqEWrB";
+
+/// Value the invalid_payload instance puts in every `DummyMsg` it sends
+pub static DUMMY_MSG_VALUE: i32 = 5;
+
+/// Context reported in v2 radio payloads, the mock graph node doesn't expose either of these
+pub static MOCK_GRAPH_NODE_VERSION: &str = "0.30.0";
+pub static MOCK_POI_ALGORITHM: &str = "legacy";
+
+/// Deployment indexing a chain that isn't built into the radio, see the custom_network check
+pub static MOCK_SUBGRAPH_CUSTOM: &str = "Qm33QnSgia4iDPWHpeY6aWxesRFdb8o5DKZUx96zZqEWrB";
+pub static MOCK_CUSTOM_NETWORK: &str = "test-chain";
+pub static MOCK_CUSTOM_NETWORK_INTERVAL: u64 = 3;
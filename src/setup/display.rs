@@ -0,0 +1,199 @@
+use colored::Colorize;
+use num_traits::ToPrimitive;
+use poi_radio_e2e_tests::{
+    compare_subgraph_attestations, reports::ComparisonOutcome, utils::RadioRuntimeConfig,
+    LocalAttestationsMap, MessagesArc, RemoteAttestationsMap, LAST_ROUND, MESSAGES,
+};
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    io::{stdout, Write},
+    time::Duration,
+};
+use tokio::time::sleep;
+
+use crate::setup::test_radio::run_test_radio;
+
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+/// Most recent blocks shown for each subgraph
+const BLOCKS_PER_SUBGRAPH: usize = 5;
+
+fn success_handler(_messages: MessagesArc) {}
+
+/// Senders of the messages received so far for each subgraph and block, by the NPOI they attested to
+type ReceivedMap = HashMap<(String, u64), HashMap<String, HashSet<String>>>;
+
+fn short(hash: &str) -> String {
+    match hash.get(..12) {
+        Some(prefix) if hash.len() > 12 => format!("{prefix}…"),
+        _ => hash.to_string(),
+    }
+}
+
+/// One line per NPOI attested for a subgraph on a block, our own NPOI and the comparison outcome go on the first one.
+/// Senders are counted from the messages as they arrive, their stake and the outcome only once a round has
+/// aggregated them
+fn render_table(
+    remote: &RemoteAttestationsMap,
+    local: &LocalAttestationsMap,
+    received: &ReceivedMap,
+    networks: &HashMap<(String, u64), String>,
+) -> String {
+    let mut table = format!(
+        "{:<14} {:<12} {:>8} {:<14} {:>7} {:>7} {:<14} {:<12}\n",
+        "SUBGRAPH", "NETWORK", "BLOCK", "NPOI", "STAKE", "SENDERS", "LOCAL NPOI", "OUTCOME"
+    );
+
+    let subgraphs = remote
+        .keys()
+        .chain(local.keys())
+        .chain(received.keys().map(|(subgraph, _)| subgraph))
+        .collect::<BTreeSet<_>>();
+    for subgraph in subgraphs {
+        let remote_blocks = remote.get(subgraph);
+        let local_blocks = local.get(subgraph);
+        let blocks = remote_blocks
+            .into_iter()
+            .flat_map(|blocks| blocks.keys())
+            .chain(local_blocks.into_iter().flat_map(|blocks| blocks.keys()))
+            .chain(
+                received
+                    .keys()
+                    .filter(|(received_subgraph, _)| received_subgraph == subgraph)
+                    .map(|(_, block)| block),
+            )
+            .collect::<BTreeSet<_>>();
+
+        for block in blocks.into_iter().rev().take(BLOCKS_PER_SUBGRAPH) {
+            let mut attestations = remote_blocks
+                .and_then(|blocks| blocks.get(block))
+                .cloned()
+                .unwrap_or_default();
+            attestations.sort_by(|a, b| b.stake_weight.cmp(&a.stake_weight));
+            let total_stake = attestations
+                .iter()
+                .map(|attestation| attestation.stake_weight.to_f64().unwrap_or_default())
+                .sum::<f64>();
+
+            let local_npoi = local_blocks
+                .and_then(|blocks| blocks.get(block))
+                .map(|attestation| short(&attestation.npoi))
+                .unwrap_or_else(|| "-".to_string());
+            let outcome = ComparisonOutcome::from(&compare_subgraph_attestations(
+                *block, subgraph, remote, local,
+            ));
+            let outcome = match outcome {
+                ComparisonOutcome::Match => outcome.to_string().green(),
+                ComparisonOutcome::Diverged => outcome.to_string().red().bold(),
+                ComparisonOutcome::Inconclusive => outcome.to_string().yellow(),
+            };
+            let network = networks
+                .get(&(subgraph.clone(), *block))
+                .map(String::as_str)
+                .unwrap_or("-");
+
+            let senders = received
+                .get(&(subgraph.clone(), *block))
+                .cloned()
+                .unwrap_or_default();
+            let mut lines = attestations
+                .iter()
+                .map(|attestation| {
+                    let share = match total_stake {
+                        total if total > 0.0 => {
+                            attestation.stake_weight.to_f64().unwrap_or_default() / total * 100.0
+                        }
+                        _ => 0.0,
+                    };
+                    let count = senders
+                        .get(&attestation.npoi)
+                        .map_or(attestation.senders.len(), HashSet::len);
+                    (
+                        short(&attestation.npoi),
+                        format!("{share:.1}%"),
+                        count.to_string(),
+                    )
+                })
+                .collect::<Vec<_>>();
+            // NPOIs received since the last round, which haven't been weighed by stake yet
+            let mut pending = senders
+                .iter()
+                .filter(|(npoi, _)| !attestations.iter().any(|a| &a.npoi == *npoi))
+                .map(|(npoi, senders)| (short(npoi), "…".to_string(), senders.len().to_string()))
+                .collect::<Vec<_>>();
+            pending.sort();
+            lines.extend(pending);
+            if lines.is_empty() {
+                lines.push(("-".to_string(), "-".to_string(), "0".to_string()));
+            }
+
+            // Only the first line of a block names it
+            let lead = [short(subgraph), network.to_string(), block.to_string()];
+            let tail = [local_npoi, outcome.to_string()];
+            for (i, (npoi, share, senders)) in lines.into_iter().enumerate() {
+                let (lead, tail) = match i {
+                    0 => (lead.clone(), tail.clone()),
+                    _ => Default::default(),
+                };
+                table.push_str(&format!(
+                    "{:<14} {:<12} {:>8} {:<14} {:>7} {:>7} {:<14} {}\n",
+                    lead[0], lead[1], lead[2], npoi, share, senders, tail[0], tail[1],
+                ));
+            }
+        }
+    }
+
+    table
+}
+
+/// Redraws the table whenever a message arrives or the radio loop finishes a round of comparing attestations
+#[tokio::main]
+async fn refresh_display() {
+    let mut last_drawn = None;
+    loop {
+        sleep(REFRESH_INTERVAL).await;
+
+        let (round, messages) = match (LAST_ROUND.get(), MESSAGES.get()) {
+            (Some(round), Some(messages)) => (
+                round.lock().unwrap().clone(),
+                messages.lock().unwrap().clone(),
+            ),
+            _ => continue,
+        };
+        let drawing = Some((round.round, messages.len()));
+        if drawing == last_drawn {
+            continue;
+        }
+        last_drawn = drawing;
+
+        let mut received = ReceivedMap::new();
+        let mut networks = round.networks.clone();
+        for (sender, msg) in &messages {
+            let key = (msg.identifier.clone(), msg.block_number);
+            let npoi = msg.payload.as_ref().unwrap().content.clone();
+            received
+                .entry(key.clone())
+                .or_default()
+                .entry(npoi)
+                .or_default()
+                .insert(sender.clone());
+            networks.insert(key, msg.network.clone());
+        }
+
+        // Clear the terminal and draw from the top left
+        print!(
+            "\x1b[2J\x1b[H{}",
+            render_table(&round.remote, &round.local, &received, &networks)
+        );
+        _ = stdout().flush();
+    }
+}
+
+/// Passively watches the radio traffic, it computes our own NPOIs to compare against but never sends them
+#[tokio::main]
+pub async fn run_display_instance() {
+    let mut config = RadioRuntimeConfig::new(false, false);
+    config.observer = true;
+
+    std::thread::spawn(refresh_display);
+    run_test_radio(&config, success_handler).await;
+}
@@ -0,0 +1,269 @@
+use chrono::Utc;
+use hex::encode;
+use num_bigint::BigUint;
+use poi_radio_e2e_tests::{
+    latency::LATENCIES,
+    rejections::REJECTIONS,
+    utils::{get_random_port, MockDeployment, MockSender, RadioRuntimeConfig},
+    MessagesArc, NetworkName, RadioPayloadMessage, RadioPayloadMessageV2, GRAPHCAST_AGENT,
+    LAST_ROUND,
+};
+use secp256k1::SecretKey;
+use std::{env, fs, os::unix::process::parent_id, time::Duration};
+use tokio::time::{interval, sleep, Instant};
+use tracing::{error, info};
+
+use crate::{
+    commands::orchestrate::{peer_id, random_key, Node},
+    setup::test_radio::{run_test_radio, send_payload},
+};
+
+const REPORT_INTERVAL: Duration = Duration::from_secs(10);
+/// Stake of the first simulated sender, each next one has that much more
+const SENDER_STAKE_STEP: &str = "100000000000000000000000";
+
+/// How much load the simulated senders put on the receiving instance
+#[derive(Clone, Copy, Debug)]
+pub struct LoadConfig {
+    pub senders: u32,
+    /// Messages each sender broadcasts per second
+    pub rate: f64,
+    /// Subgraph deployments the messages are spread across, each one a content topic
+    pub topics: u32,
+}
+
+fn success_handler(_messages: MessagesArc) {}
+
+fn load_topics(count: u32) -> Vec<String> {
+    (0..count).map(|i| format!("QmLoadTopic{i:035}")).collect()
+}
+
+/// Resident memory of this process in KiB, read from procfs so only available on Linux
+fn resident_memory_kib() -> Option<u64> {
+    fs::read_to_string("/proc/self/status")
+        .ok()?
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))?
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// Attests to the subgraphs in turn at `rate` messages per second, moving on to the next block once it has
+/// attested to all of them, and broadcasts every attestation through the agent. Stops the publisher once the
+/// instance that started it is gone, as nothing else would
+#[tokio::main]
+async fn publish(rate: f64, deployments: Vec<MockDeployment>, npoi: String) {
+    if rate <= 0.0 {
+        error!("Publishing rate has to be above 0, got {rate}");
+        return;
+    }
+    if deployments.is_empty() {
+        error!("No subgraphs to publish attestations for, pass them with --subgraph");
+        return;
+    }
+    while GRAPHCAST_AGENT.get().is_none() {
+        sleep(Duration::from_secs(1)).await;
+    }
+
+    let parent = parent_id();
+    let mut ticker = interval(Duration::from_secs_f64(1.0 / rate));
+    for sent in 0.. {
+        ticker.tick().await;
+        if parent_id() != parent {
+            info!("The instance that started this publisher is gone, stopping");
+            std::process::exit(0);
+        }
+
+        let deployment = &deployments[sent % deployments.len()];
+        let block = (sent / deployments.len()) as u64;
+        let id = &deployment.ipfs_hash;
+        // Only a v2 payload can name a chain registered through RADIO_NETWORKS, which the SDK sends as unknown
+        _ = match NetworkName::from_string(&deployment.network).to_wire() {
+            (network, None) => {
+                let payload = RadioPayloadMessage::new(id.clone(), npoi.clone());
+                send_payload(id, network, block, payload).await
+            }
+            (network, Some(chain)) => {
+                let payload = RadioPayloadMessageV2::new(
+                    id.clone(),
+                    npoi.clone(),
+                    String::new(),
+                    String::new(),
+                    String::new(),
+                )
+                .with_network(chain)
+                .with_sent_at(Utc::now().timestamp_millis());
+                send_payload(id, network, block, payload).await
+            }
+        };
+    }
+}
+
+/// A simulated sender broadcasting its attestations, signing with the sender's key. The subgraphs index the default
+/// networks unless a network is given for all of them
+#[tokio::main]
+pub async fn run_publisher_instance(rate: f64, subgraphs: Vec<String>, network: Option<String>) {
+    let deployments = match &network {
+        Some(network) => subgraphs
+            .iter()
+            .map(|ipfs_hash| MockDeployment::new(ipfs_hash, network, 0))
+            .collect(),
+        None => MockDeployment::defaults(&subgraphs),
+    };
+
+    let mut config = RadioRuntimeConfig::new(true, false);
+    config.observer = true;
+    config.private_key = env::var("PRIVATE_KEY").ok();
+    config.subgraphs = Some(subgraphs);
+    config.deployments = Some(deployments.clone());
+
+    let npoi = config.poi.clone();
+    std::thread::spawn(move || publish(rate, deployments, npoi));
+    run_test_radio(&config, success_handler).await;
+}
+
+/// Fixes the node key and Waku port this instance's node starts with, and returns the address other nodes can
+/// boot from it with. Has to be called before the radio starts
+pub fn own_boot_node_address() -> String {
+    let node_key = env::var("WAKU_NODE_KEY")
+        .ok()
+        .and_then(|key| hex::decode(key).ok())
+        .and_then(|key| SecretKey::from_slice(&key).ok())
+        .unwrap_or_else(random_key);
+    let waku_port = env::var("WAKU_PORT").unwrap_or_else(|_| get_random_port());
+    env::set_var("WAKU_NODE_KEY", encode(node_key.secret_bytes()));
+    env::set_var("WAKU_PORT", &waku_port);
+
+    format!("/ip4/127.0.0.1/tcp/{waku_port}/p2p/{}", peer_id(&node_key))
+}
+
+/// Starts a publisher for every simulated sender once this instance's node is up, booting them from it, and
+/// keeps them running for as long as this instance does
+#[tokio::main]
+pub async fn start_publishers(
+    senders: Vec<MockSender>,
+    rate: f64,
+    subgraphs: Vec<String>,
+    network: Option<String>,
+    boot_node_address: String,
+) {
+    while GRAPHCAST_AGENT.get().is_none() {
+        sleep(Duration::from_secs(1)).await;
+    }
+
+    let mut args = vec![
+        "--instance".to_string(),
+        "publisher".to_string(),
+        "--rate".to_string(),
+        rate.to_string(),
+    ];
+    for subgraph in subgraphs {
+        args.extend(["--subgraph".to_string(), subgraph]);
+    }
+    if let Some(network) = network {
+        args.extend(["--network".to_string(), network]);
+    }
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+
+    let mut publishers = Vec::new();
+    for (i, sender) in senders.iter().enumerate() {
+        let publisher = Node::spawn(
+            &format!("publisher-{}", i + 1),
+            &args,
+            &[
+                ("PRIVATE_KEY", encode(sender.wallet.signer().to_bytes())),
+                ("WAKU_PORT", get_random_port()),
+                ("BOOT_NODE_ADDRESSES", boot_node_address.clone()),
+            ],
+        );
+        match publisher {
+            Ok(publisher) => publishers.push(publisher),
+            Err(e) => error!(
+                "Could not start a publisher for {}: {e}",
+                sender.graphcast_id()
+            ),
+        }
+    }
+
+    loop {
+        sleep(REPORT_INTERVAL).await;
+    }
+}
+
+/// Periodically reports the throughput of the handler, how long the radio loop took to aggregate the stored
+/// messages and how much memory this instance holds on to
+#[tokio::main]
+async fn report_load(load: LoadConfig) {
+    let target = f64::from(load.senders) * load.rate;
+    let mut last_observed = 0;
+    let mut last_report = Instant::now();
+
+    loop {
+        sleep(REPORT_INTERVAL).await;
+        let (rejections, last_round) = match (REJECTIONS.get(), LAST_ROUND.get()) {
+            (Some(rejections), Some(last_round)) => (rejections, last_round),
+            _ => continue,
+        };
+
+        let observed = rejections.lock().unwrap().observed;
+        let throughput = (observed - last_observed) as f64 / last_report.elapsed().as_secs_f64();
+        last_observed = observed;
+        last_report = Instant::now();
+
+        let aggregation = {
+            let round = last_round.lock().unwrap();
+            match round.round {
+                0 => "no messages aggregated yet".to_string(),
+                _ => format!(
+                    "process_messages took {}ms over {} stored messages",
+                    round.aggregation_time.as_millis(),
+                    round.aggregated
+                ),
+            }
+        };
+        let delivery = LATENCIES
+            .get()
+            .and_then(|latencies| latencies.lock().unwrap().percentiles())
+            .map(|percentiles| format!("p95 delivery in {}ms", percentiles.p95))
+            .unwrap_or_else(|| "no delivery latency yet".to_string());
+
+        info!(
+            "Load of {} senders over {} topics: {observed} messages received, {throughput:.1}/s of {target:.1}/s targeted, {delivery}, {aggregation}, {} KiB resident",
+            load.senders,
+            load.topics,
+            resident_memory_kib()
+                .map(|kib| kib.to_string())
+                .unwrap_or_else(|| "unknown".to_string()),
+        );
+    }
+}
+
+/// Receives attestations from a fleet of publishers, each one its own process broadcasting with the key of a
+/// simulated sender with its own stake, and reports how well aggregation keeps up with them. It never sends
+/// attestations itself
+#[tokio::main]
+pub async fn run_load_instance(load: LoadConfig) {
+    let topics = load_topics(load.topics.max(1));
+    let deployments = MockDeployment::defaults(&topics);
+    let step = SENDER_STAKE_STEP.parse::<BigUint>().unwrap();
+    let senders = (1..=load.senders)
+        .map(|i| MockSender::generate((&step * i).to_string()))
+        .collect::<Vec<_>>();
+
+    let boot_node_address = own_boot_node_address();
+
+    let mut config = RadioRuntimeConfig::new(false, false);
+    config.observer = true;
+    config.subgraphs = Some(topics.clone());
+    config.deployments = Some(deployments);
+    config.senders = senders.clone();
+
+    std::thread::spawn(move || {
+        start_publishers(senders, load.rate, topics, None, boot_node_address)
+    });
+    std::thread::spawn(move || report_load(load));
+    run_test_radio(&config, success_handler).await;
+}
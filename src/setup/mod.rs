@@ -1,4 +1,7 @@
 pub mod basic;
+pub mod boot;
 pub mod constants;
+pub mod display;
 pub mod invalid_payload;
+pub mod load;
 pub mod test_radio;
@@ -1,41 +1,327 @@
+use chrono::Utc;
 use colored::*;
 use ethers::signers::LocalWallet;
+use ethers_core::types::transaction::eip712::Eip712;
 /// Radio specific query function to fetch Proof of Indexing for each allocated subgraph
 use graphcast_sdk::graphcast_agent::GraphcastAgent;
 use graphcast_sdk::graphql::client_network::query_network_subgraph;
 use graphcast_sdk::graphql::client_registry::query_registry_indexer;
-use graphcast_sdk::{graphcast_id_address, read_boot_node_addresses, NetworkName};
+use graphcast_sdk::graphql::QueryError;
+use graphcast_sdk::{graphcast_id_address, read_boot_node_addresses};
 use hex::encode;
 use num_bigint::BigUint;
 use num_traits::Zero;
-use partial_application::partial;
+use poi_radio_e2e_tests::latency::{LatencyLog, LATENCIES};
+use poi_radio_e2e_tests::rejections::{
+    record_rejection, Rejection, RejectionLog, RejectionReason, REJECTIONS,
+};
+use poi_radio_e2e_tests::{
+    attestation_handler, compare_attestations, compare_subgraph_attestations, most_attested_npoi,
+    process_messages, save_local_attestation, Attestation, BlockClock, BlockPointer, CompareError,
+    Divergence, DummyMsg, LastRound, LocalAttestationsMap, MessagesArc, NetworkName,
+    RadioPayloadMessage, RadioPayloadMessageV2, RemoteAttestationsMap, SentMessage, TopicUpdate,
+    DIVERGENCES, GRAPHCAST_AGENT, LAST_ROUND, MESSAGES, NETWORKS, PAYLOAD_VERSIONS, SENT_MESSAGES,
+    TOPIC_UPDATES,
+};
 use poi_radio_e2e_tests::{
-    attestation_handler, compare_attestations, process_messages, save_local_attestation,
-    Attestation, BlockClock, BlockPointer, CompareError, DummyMsg, LocalAttestationsMap,
-    MessagesArc, RadioPayloadMessage, GRAPHCAST_AGENT, MESSAGES, NETWORKS,
+    notifiers::{Notification, NotifierConfig, Notifiers, Severity},
+    reports::{write_run_report, write_run_report_on_panic, RunHistory, RUN_HISTORY},
+    SubgraphStatus,
 };
+use prost::Message;
 use rand::{thread_rng, Rng};
 use secp256k1::SecretKey;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::env;
+use std::fmt::Debug;
+use std::panic;
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex as SyncMutex};
-use std::{thread::sleep, time::Duration};
+use std::{
+    thread::sleep,
+    time::{Duration, Instant},
+};
 use tokio::sync::Mutex as AsyncMutex;
 use tracing::log::warn;
 use tracing::{debug, error, info};
 
-use crate::graphql::{query_graph_node_poi, update_network_chainheads};
-use crate::setup::constants::{MOCK_SUBGRAPH_GOERLI, MOCK_SUBGRAPH_MAINNET};
+use crate::graphql::{
+    query_graph_node_poi, query_graph_node_pois, update_network_chainheads, BlockHashResolver,
+};
+use crate::setup::constants::{
+    DUMMY_MSG_VALUE, MOCK_GRAPH_NODE_VERSION, MOCK_POI_ALGORITHM, MOCK_SUBGRAPH_GOERLI,
+    MOCK_SUBGRAPH_MAINNET,
+};
 use poi_radio_e2e_tests::utils::{
-    empty_attestation_handler, generate_random_address, get_random_port, setup_mock_env_vars,
-    setup_mock_server, RadioRuntimeConfig,
+    allocations_at, empty_attestation_handler, generate_random_address, get_random_port,
+    scheduled_deployments, setup_mock_env_vars, setup_mock_server, MockDeployment,
+    RadioRuntimeConfig,
 };
 
+/// Broadcasts a payload of any of the radio's message types through the agent
+pub(crate) async fn send_payload<T>(
+    id: &str,
+    network: graphcast_sdk::NetworkName,
+    block: u64,
+    payload: T,
+) -> Result<String, anyhow::Error>
+where
+    T: Message + Eip712 + Default + Clone + Debug + 'static,
+{
+    info!("{}: {:?}", "Attempting to send message".magenta(), payload);
+    let sent = GRAPHCAST_AGENT
+        .get()
+        .unwrap()
+        .send_message(id.to_string(), network, block, Some(payload))
+        .await;
+    match &sent {
+        Ok(sent) => info!("{}: {}", "Sent message id".green(), sent),
+        Err(e) => error!("{}: {}", "Failed to send message".red(), e),
+    }
+
+    sent
+}
+
+/// Drops received messages whose block hash differs from the one our own graph node has for that block and records
+/// them as rejections. Messages whose block can't be resolved yet are kept and checked again on the next round
+pub async fn reject_unverified_block_hashes(
+    messages: &MessagesArc,
+    resolver: &mut BlockHashResolver,
+) {
+    let received = messages.lock().unwrap().clone();
+    let mut mismatched = HashSet::new();
+
+    for (i, (sender, msg)) in received.iter().enumerate() {
+        let network = NetworkName::from_string(&msg.network);
+        let local_hash = match resolver
+            .verify(&network, msg.block_number, &msg.block_hash)
+            .await
+        {
+            Ok(local_hash) => local_hash,
+            Err(e) => {
+                warn!(
+                    "Could not resolve block {} on {} to verify its hash: {e}",
+                    msg.block_number, network
+                );
+                continue;
+            }
+        };
+
+        if let Some(local_hash) = local_hash {
+            record_rejection(Rejection::new(
+                Some(sender.clone()),
+                RejectionReason::LocalBlockHashMismatch,
+                format!(
+                    "Block {} on {} has hash {} locally, message has {}",
+                    msg.block_number, network, local_hash, msg.block_hash
+                ),
+            ));
+            mismatched.insert(i);
+        }
+    }
+
+    // The handler only ever appends, so the indices of the messages we checked are still valid
+    let mut i = 0;
+    messages.lock().unwrap().retain(|_| {
+        i += 1;
+        !mismatched.contains(&(i - 1))
+    });
+}
+
+/// Binary searches the blocks we hold remote attestations for below `diverged_block`, querying our own POI on each,
+/// for the first one where our NPOI departed from the stake-weighted consensus. Once a POI diverges every later one
+/// does too, so a match means the divergence is further up and a mismatch means it is at or below that block
+pub async fn bisect_divergence(
+    ipfs_hash: &str,
+    network: &NetworkName,
+    diverged_block: u64,
+    remote: &RemoteAttestationsMap,
+    graph_node_endpoint: &str,
+    resolver: &mut BlockHashResolver,
+    indexer: Option<String>,
+) -> Result<Divergence, QueryError> {
+    let mut consensus = remote
+        .get(ipfs_hash)
+        .map(|blocks| {
+            blocks
+                .iter()
+                .filter(|(block, _)| **block < diverged_block)
+                .filter_map(|(block, attestations)| {
+                    most_attested_npoi(attestations).map(|npoi| (*block, npoi.clone()))
+                })
+                .collect::<Vec<(u64, String)>>()
+        })
+        .unwrap_or_default();
+    consensus.sort_unstable_by_key(|(block, _)| *block);
+
+    let (mut low, mut high) = (0, consensus.len());
+    while low < high {
+        let mid = low + (high - low) / 2;
+        let (block, npoi) = &consensus[mid];
+        let block_hash = resolver.resolve(network, *block).await?;
+        let local_npoi = query_graph_node_poi(
+            graph_node_endpoint.to_string(),
+            ipfs_hash.to_string(),
+            block_hash,
+            (*block).try_into().unwrap(),
+            indexer.clone(),
+        )
+        .await?;
+
+        debug!(
+            "Bisecting {}: block {} {}",
+            ipfs_hash,
+            block,
+            if &local_npoi == npoi {
+                "matches"
+            } else {
+                "diverged"
+            }
+        );
+        if &local_npoi == npoi {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+
+    Ok(Divergence {
+        identifier: ipfs_hash.to_string(),
+        first_diverged_block: consensus
+            .get(low)
+            .map_or(diverged_block, |(block, _)| *block),
+        last_matching_block: low.checked_sub(1).map(|i| consensus[i].0),
+    })
+}
+
+/// Bisects every deployment whose NPOI disagrees with the consensus on `attestation_block`, recording what it finds
+async fn investigate_divergences(
+    attestation_block: u64,
+    remote: &RemoteAttestationsMap,
+    local: &LocalAttestationsMap,
+    statuses: &HashMap<String, SubgraphStatus>,
+    graph_node_endpoint: &str,
+    resolver: &mut BlockHashResolver,
+    indexer: Option<String>,
+) -> Vec<Divergence> {
+    let mut divergences = Vec::new();
+    for ipfs_hash in local.keys() {
+        let diverged = matches!(
+            compare_subgraph_attestations(attestation_block, ipfs_hash, remote, local),
+            Err(CompareError::Critical(_))
+        );
+        let network = statuses
+            .get(ipfs_hash)
+            .and_then(|status| status.primary_chain())
+            .map(|chain| chain.network.clone());
+        let network = match network {
+            Some(network) if diverged => network,
+            _ => continue,
+        };
+
+        match bisect_divergence(
+            ipfs_hash,
+            &network,
+            attestation_block,
+            remote,
+            graph_node_endpoint,
+            resolver,
+            indexer.clone(),
+        )
+        .await
+        {
+            Ok(divergence) => {
+                error!(
+                    "POIs for subgraph {} first diverged on block {}, last matching block: {:?}",
+                    ipfs_hash, divergence.first_diverged_block, divergence.last_matching_block
+                );
+                DIVERGENCES
+                    .get()
+                    .unwrap()
+                    .lock()
+                    .unwrap()
+                    .push(divergence.clone());
+                divergences.push(divergence);
+            }
+            Err(e) => error!("Could not bisect the divergence of {ipfs_hash}: {e}"),
+        }
+    }
+
+    divergences
+}
+
+/// Moves the agent's content topics onto the deployments the Indexer is allocated to once those have changed.
+/// Local attestations of closed allocations are dropped along with their topics, so they stop being compared
+async fn follow_allocations(
+    network_subgraph: &str,
+    indexer: &str,
+    allocated: &mut Vec<String>,
+    local_attestations: &AsyncMutex<LocalAttestationsMap>,
+) {
+    let allocations = match query_network_subgraph(
+        network_subgraph.to_string(),
+        indexer.to_string(),
+    )
+    .await
+    {
+        Ok(network) => network.indexer_allocations(),
+        Err(e) => {
+            warn!("Could not query the Indexer's allocations, keeping the current content topics: {e}");
+            return;
+        }
+    };
+
+    let opened: Vec<String> = allocations
+        .iter()
+        .filter(|ipfs_hash| !allocated.contains(ipfs_hash))
+        .cloned()
+        .collect();
+    let closed: Vec<String> = allocated
+        .iter()
+        .filter(|ipfs_hash| !allocations.contains(ipfs_hash))
+        .cloned()
+        .collect();
+    if opened.is_empty() && closed.is_empty() {
+        return;
+    }
+
+    info!(
+        "Allocations changed, following {} new and dropping {} closed deployments: {:?} {:?}",
+        opened.len(),
+        closed.len(),
+        opened,
+        closed
+    );
+    let agent = GRAPHCAST_AGENT.get().unwrap();
+    agent.update_content_topics(allocations.clone()).await;
+    local_attestations
+        .lock()
+        .await
+        .retain(|ipfs_hash, _| allocations.contains(ipfs_hash));
+    *allocated = allocations;
+
+    let topics = agent.content_identifiers().await;
+    let sent_before = SENT_MESSAGES.get().unwrap().lock().unwrap().len();
+    TOPIC_UPDATES
+        .get()
+        .unwrap()
+        .lock()
+        .unwrap()
+        .push(TopicUpdate {
+            opened,
+            closed,
+            topics,
+            updated_at: Utc::now().timestamp_millis(),
+            sent_before,
+        });
+}
+
 pub async fn run_test_radio<F>(config: &RadioRuntimeConfig, success_handler: F)
 where
     F: Fn(MessagesArc),
 {
     let mut block_number = 0;
+    let mut round = 0;
     let indexer_address = config
         .indexer_address
         .clone()
@@ -46,16 +332,26 @@ where
         .clone()
         .unwrap_or(generate_random_address());
 
+    let subgraphs = config.subgraphs.clone().unwrap_or(vec![
+        MOCK_SUBGRAPH_MAINNET.to_string(),
+        MOCK_SUBGRAPH_GOERLI.to_string(),
+    ]);
+    // Scheduled allocations are indexed from the start, graph node doesn't wait for the Indexer to allocate
+    let deployments = config.deployments.clone().unwrap_or_else(|| {
+        MockDeployment::defaults(&scheduled_deployments(
+            &subgraphs,
+            &config.allocation_schedule,
+        ))
+    });
+
     let mock_server_uri = setup_mock_server(
         block_number,
+        round,
         &indexer_address,
         &graphcast_id,
-        &config.subgraphs.clone().unwrap_or(vec![
-            MOCK_SUBGRAPH_MAINNET.to_string(),
-            MOCK_SUBGRAPH_GOERLI.to_string(),
-        ]),
-        &config.indexer_stake,
-        &config.poi,
+        &allocations_at(&subgraphs, &config.allocation_schedule, block_number),
+        &deployments,
+        config,
     )
     .await;
     setup_mock_env_vars(&mock_server_uri);
@@ -72,15 +368,18 @@ where
     let wait_block_duration = 2;
 
     let wallet = private_key.parse::<LocalWallet>().unwrap();
-    let mut rng = thread_rng();
-    let mut private_key = [0u8; 32];
-    rng.fill(&mut private_key[..]);
-
-    let private_key = SecretKey::from_slice(&private_key).expect("Error parsing secret key");
-    let private_key_hex = encode(private_key.secret_bytes());
+    let private_key_hex = config.private_key.clone().unwrap_or_else(|| {
+        let mut rng = thread_rng();
+        let mut private_key = [0u8; 32];
+        rng.fill(&mut private_key[..]);
+
+        let private_key = SecretKey::from_slice(&private_key).expect("Error parsing secret key");
+        encode(private_key.secret_bytes())
+    });
     env::set_var("PRIVATE_KEY", &private_key_hex);
 
     let private_key = env::var("PRIVATE_KEY").unwrap();
+    let self_address = graphcast_id_address(&private_key.parse::<LocalWallet>().unwrap());
 
     // TODO: Add something random and unique here to avoid noise form other operators
     let radio_name: &str = "test-poi-radio";
@@ -98,13 +397,10 @@ where
         &graph_node_endpoint,
         read_boot_node_addresses(),
         Some("5"),
-        config.subgraphs.clone().unwrap_or(vec![
-            MOCK_SUBGRAPH_MAINNET.to_string(),
-            MOCK_SUBGRAPH_GOERLI.to_string(),
-        ]),
-        None,
+        subgraphs.clone(),
+        env::var("WAKU_NODE_KEY").ok(),
         None,
-        Some(get_random_port()),
+        Some(env::var("WAKU_PORT").unwrap_or_else(|_| get_random_port())),
         None,
     )
     .await
@@ -112,6 +408,14 @@ where
 
     _ = GRAPHCAST_AGENT.set(graphcast_agent);
     _ = MESSAGES.set(Arc::new(SyncMutex::new(vec![])));
+    _ = REJECTIONS.set(Arc::new(SyncMutex::new(RejectionLog::default())));
+    _ = LATENCIES.set(Arc::new(SyncMutex::new(LatencyLog::default())));
+    _ = PAYLOAD_VERSIONS.set(Arc::new(SyncMutex::new(HashMap::new())));
+    _ = SENT_MESSAGES.set(Arc::new(SyncMutex::new(vec![])));
+    _ = TOPIC_UPDATES.set(Arc::new(SyncMutex::new(vec![])));
+    _ = DIVERGENCES.set(Arc::new(SyncMutex::new(vec![])));
+    _ = RUN_HISTORY.set(Arc::new(SyncMutex::new(RunHistory::default())));
+    _ = LAST_ROUND.set(Arc::new(SyncMutex::new(LastRound::default())));
 
     if config.is_setup_instance {
         GRAPHCAST_AGENT
@@ -123,12 +427,34 @@ where
         GRAPHCAST_AGENT
             .get()
             .unwrap()
-            .register_handler(Arc::new(AsyncMutex::new(attestation_handler())))
+            .register_handler(Arc::new(AsyncMutex::new(attestation_handler(self_address))))
             .expect("Could not register handler");
     };
 
     let mut block_store: HashMap<NetworkName, BlockClock> = HashMap::new();
     let mut network_chainhead_blocks: HashMap<NetworkName, BlockPointer> = HashMap::new();
+    let mut block_hash_resolver = BlockHashResolver::new(graph_node_endpoint.clone());
+
+    let mut notifier_configs = config.notifiers.clone();
+    notifier_configs.extend(NotifierConfig::from_env());
+    let notifiers = Notifiers::new(&notifier_configs);
+
+    let report_path = config.report_path.clone().or_else(|| {
+        env::var("RADIO_REPORT_PATH")
+            .ok()
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from)
+    });
+    if let Some(path) = report_path.clone() {
+        // A diverged POI can end the run with a panic, report on it before it unwinds
+        let default_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if let Err(e) = write_run_report_on_panic(&path) {
+                error!("Could not write the run report to {}: {e}", path.display());
+            }
+            default_hook(info);
+        }));
+    }
     let local_attestations: Arc<AsyncMutex<LocalAttestationsMap>> =
         Arc::new(AsyncMutex::new(HashMap::new()));
 
@@ -145,9 +471,21 @@ where
         my_address, my_stake
     );
 
+    let mut allocated = subgraphs.clone();
+
     // Main loop for sending messages, can factor out
     // and take radio specific query and parsing for radioPayload
     loop {
+        if let Some(indexer) = &my_address {
+            follow_allocations(
+                &network_subgraph,
+                indexer,
+                &mut allocated,
+                &local_attestations,
+            )
+            .await;
+        }
+
         // Update all the chainheads of the network
         // Also get a hash map returned on the subgraph mapped to network name and latest block
         let subgraph_network_latest_blocks = match update_network_chainheads(
@@ -159,6 +497,7 @@ where
             Ok(res) => res,
             Err(e) => {
                 error!("Could not query indexing statuses, pull again later: {e}");
+                sleep(Duration::from_secs(5));
                 continue;
             }
         };
@@ -176,15 +515,42 @@ where
 
         info!("debugging with style {:?}", subgraph_network_latest_blocks);
 
+        let mut due_attestations: HashMap<(String, u64), Vec<(String, NetworkName)>> =
+            HashMap::new();
+
+        let multi_chain_deployments: HashMap<String, NetworkName> = subgraph_network_latest_blocks
+            .iter()
+            .filter(|(_, status)| status.is_multi_chain())
+            .filter_map(|(id, status)| {
+                status
+                    .primary_chain()
+                    .map(|chain| (id.clone(), chain.network.clone()))
+            })
+            .collect();
+
         for id in identifiers {
             // Get the indexing network of the deployment
             // and update the NETWORK message block
             let (network_name, latest_block) = match subgraph_network_latest_blocks.get(&id.clone())
             {
-                Some(network_block) => (
-                    NetworkName::from_string(&network_block.network.clone()),
-                    network_block.block.clone(),
-                ),
+                Some(network_block) => {
+                    // Failed or unsynced deployments don't have POIs worth attesting to
+                    if let Some(reason) = network_block.skip_reason() {
+                        warn!("Not attesting to {}: {}", id, reason);
+                        continue;
+                    }
+
+                    match network_block.primary_chain() {
+                        Some(chain) => (chain.network.clone(), chain.latest_block.clone()),
+                        None => {
+                            error!(
+                                "Graph node reports no chains for subgraph deployment {}",
+                                id
+                            );
+                            continue;
+                        }
+                    }
+                }
                 None => {
                     error!("Could not query the subgraph's indexing network, check Graph node's indexing statuses of subgraph deployment {}", id.clone());
                     continue;
@@ -192,20 +558,30 @@ where
             };
 
             // Get the examination frequency of the network
-            let examination_frequency = match NETWORKS
-                .iter()
-                .find(|n| n.name.to_string() == network_name.to_string())
-            {
+            let examination_frequency = match NETWORKS.iter().find(|n| n.name == network_name) {
                 Some(n) => n.interval,
                 None => {
-                    warn!("Subgraph is indexing an unsupported network, please report an issue on https://github.com/graphops/graphcast-rs");
+                    warn!("Subgraph is indexing an unsupported network {network_name}, register it through RADIO_NETWORKS or report an issue on https://github.com/graphops/graphcast-rs");
                     continue;
                 }
             };
 
-            // Calculate the block to send message about
+            // Calculate the block to send message about. A deployment lagging the chainhead has no POI for the
+            // blocks it hasn't indexed yet, so it attests the latest interval block it has indexed instead
             let message_block = match network_chainhead_blocks.get(&network_name) {
-                Some(BlockPointer { hash: _, number }) => number - number % examination_frequency,
+                Some(BlockPointer { hash: _, number }) => {
+                    let indexed = (*number).min(latest_block.number);
+                    if indexed < *number {
+                        debug!(
+                            "{} has indexed up to block {} of {}, {} blocks behind the chainhead",
+                            id,
+                            indexed,
+                            network_name,
+                            number - indexed
+                        );
+                    }
+                    indexed - indexed % examination_frequency
+                }
                 None => {
                     error!(
                         "Could not get the chainhead block number on network {} and cannot determine the block to send message about",
@@ -215,34 +591,17 @@ where
                 }
             };
 
-            let block_clock = block_store
-                .entry(network_name)
-                .or_insert_with(|| BlockClock {
-                    current_block: 0,
-                    compare_block: 0,
-                });
+            let block_clock =
+                block_store
+                    .entry(network_name.clone())
+                    .or_insert_with(|| BlockClock {
+                        current_block: 0,
+                        compare_block: 0,
+                    });
 
-            // Wait a bit before querying information on the current block
+            // Wait a bit before querying information on the current block. The mock only advances once per loop
+            // below, advancing it here as well would move it once per waiting identifier and skip blocks
             if block_clock.current_block == message_block {
-                if block_number < 20 {
-                    block_number += 1;
-                } else {
-                    block_number = 0;
-                    MESSAGES.get().unwrap().lock().unwrap().clear()
-                }
-                setup_mock_server(
-                    block_number,
-                    &indexer_address,
-                    &graphcast_id,
-                    &config.subgraphs.clone().unwrap_or(vec![
-                        MOCK_SUBGRAPH_MAINNET.to_string(),
-                        MOCK_SUBGRAPH_GOERLI.to_string(),
-                    ]),
-                    &config.indexer_stake,
-                    &config.poi,
-                )
-                .await;
-                sleep(Duration::from_secs(5));
                 continue;
             }
 
@@ -264,39 +623,120 @@ where
                 debug!("{}", "Comparing attestations".magenta());
 
                 debug!("{}{:?}", "Messages: ".magenta(), MESSAGES);
+                debug!(
+                    "{}{:?}",
+                    "Rejections per sender: ".magenta(),
+                    REJECTIONS.get().unwrap().lock().unwrap().per_sender
+                );
 
+                reject_unverified_block_hashes(MESSAGES.get().unwrap(), &mut block_hash_resolver)
+                    .await;
+
+                let aggregated = MESSAGES.get().unwrap().lock().unwrap().len();
+                let aggregation_started = Instant::now();
                 let remote_attestations = process_messages(
                     Arc::clone(MESSAGES.get().unwrap()),
                     &format!("{}{}", &mock_server_uri, "/graphcast-registry"),
                     &network_subgraph,
+                    &multi_chain_deployments,
                 )
                 .await;
+                let aggregation_time = aggregation_started.elapsed();
                 match remote_attestations {
                     Ok(remote_attestations) => {
+                        // Checks end the run from their success handler, so the report is brought up to date first
+                        if let Some(path) = &report_path {
+                            if let Err(e) = write_run_report(path) {
+                                error!("Could not write the run report to {}: {e}", path.display());
+                            }
+                        }
                         success_handler(Arc::clone(MESSAGES.get().unwrap()));
 
-                        match compare_attestations(
-                            block_clock.compare_block - wait_block_duration,
-                            remote_attestations,
+                        let attestation_block = block_clock.compare_block - wait_block_duration;
+                        let comparison = compare_attestations(
+                            attestation_block,
+                            remote_attestations.clone(),
                             Arc::clone(&local_attestations),
                         )
-                        .await
+                        .await;
+                        let local = local_attestations.lock().await.clone();
                         {
+                            let mut history = RUN_HISTORY.get().unwrap().lock().unwrap();
+                            history.record_round(
+                                attestation_block,
+                                &remote_attestations,
+                                &local,
+                                &subgraph_network_latest_blocks,
+                            );
+                            history.record_latencies(&LATENCIES.get().unwrap().lock().unwrap());
+                        }
+                        {
+                            let networks = MESSAGES
+                                .get()
+                                .unwrap()
+                                .lock()
+                                .unwrap()
+                                .iter()
+                                .map(|(_, msg)| {
+                                    (
+                                        (msg.identifier.clone(), msg.block_number),
+                                        msg.network.clone(),
+                                    )
+                                })
+                                .collect();
+                            let mut last_round = LAST_ROUND.get().unwrap().lock().unwrap();
+                            *last_round = LastRound {
+                                round: last_round.round + 1,
+                                attestation_block,
+                                remote: remote_attestations.clone(),
+                                local: local.clone(),
+                                networks,
+                                aggregated,
+                                aggregation_time,
+                            };
+                        }
+
+                        match comparison {
                             Ok(msg) => {
                                 debug!("{}", msg.green().bold());
                             }
-                            Err(err) => match err {
-                                CompareError::Critical(_) => {
-                                    if config.panic_if_poi_diverged {
-                                        panic!("{}", err);
-                                    } else {
-                                        error!("{}", err);
+                            Err(err) => {
+                                let (severity, divergences) = match err {
+                                    CompareError::Critical(_) => (
+                                        Severity::Critical,
+                                        investigate_divergences(
+                                            attestation_block,
+                                            &remote_attestations,
+                                            &local,
+                                            &subgraph_network_latest_blocks,
+                                            &graph_node_endpoint,
+                                            &mut block_hash_resolver,
+                                            my_address.clone(),
+                                        )
+                                        .await,
+                                    ),
+                                    CompareError::NonCritical(_) => {
+                                        (Severity::NonCritical, Vec::new())
                                     }
+                                };
+                                let notification = Notification::new(
+                                    severity,
+                                    &err.to_string(),
+                                    attestation_block,
+                                    &remote_attestations,
+                                    &local,
+                                    &subgraph_network_latest_blocks,
+                                    divergences,
+                                );
+                                notifiers.notify(&notification).await;
+
+                                match err {
+                                    CompareError::Critical(_) if config.panic_if_poi_diverged => {
+                                        panic!("{}", err)
+                                    }
+                                    _ => error!("{}", err),
                                 }
-                                CompareError::NonCritical(_) => {
-                                    error!("{}", err);
-                                }
-                            },
+                            }
                         }
                     }
                     Err(err) => {
@@ -309,9 +749,6 @@ where
                 }
             }
 
-            let poi_query =
-                partial!( query_graph_node_poi => graph_node_endpoint.clone(), id.clone(), _, _);
-
             debug!(
                 "Checking latest block number and the message block: {0} >?= {message_block}",
                 latest_block.number
@@ -334,68 +771,110 @@ where
                 if config.invalid_payload {
                     // Send dummy msg
                     debug!("Sending dummy message");
-                    let radio_message = DummyMsg::new(id.clone(), 5);
-                    info!(
-                        "{}: {:?}",
-                        "Attempting to send message".magenta(),
-                        radio_message
-                    );
-
-                    match GRAPHCAST_AGENT
-                        .get()
-                        .unwrap()
-                        .send_message(id.clone(), network_name, message_block, Some(radio_message))
-                        .await
-                    {
-                        Ok(sent) => {
-                            info!("{}: {}", "Sent message id".green(), sent);
-                        }
-                        Err(e) => error!("{}: {}", "Failed to send message".red(), e),
-                    };
+                    let radio_message = DummyMsg::new(id.clone(), DUMMY_MSG_VALUE);
+                    let (sdk_network, _) = network_name.to_wire();
+                    _ = send_payload(&id, sdk_network, message_block, radio_message).await;
 
                     continue;
                 }
 
-                match poi_query(block_hash.clone(), message_block.try_into().unwrap()).await {
-                    Ok(content) => {
-                        let attestation = Attestation {
-                            npoi: content.clone(),
-                            stake_weight: my_stake.clone(),
-                            senders: Vec::new(),
-                        };
-
-                        save_local_attestation(
-                            &mut *local_attestations.lock().await,
-                            attestation,
-                            id.clone(),
-                            message_block,
-                        );
+                due_attestations
+                    .entry((block_hash, message_block))
+                    .or_default()
+                    .push((id.clone(), network_name.clone()));
+            }
+        }
 
-                        let radio_message = RadioPayloadMessage::new(id.clone(), content.clone());
-                        info!(
-                            "{}: {:?}",
-                            "Attempting to send message".magenta(),
-                            radio_message
-                        );
+        // Deployments due at the same block share a single POI request
+        for ((block_hash, message_block), deployments) in due_attestations {
+            let ipfs_hashes: Vec<String> = deployments.iter().map(|(id, _)| id.clone()).collect();
+            let pois = match ipfs_hashes.as_slice() {
+                [id] => query_graph_node_poi(
+                    graph_node_endpoint.clone(),
+                    id.clone(),
+                    block_hash.clone(),
+                    message_block.try_into().unwrap(),
+                    my_address.clone(),
+                )
+                .await
+                .map(|poi| HashMap::from([(id.clone(), poi)])),
+                _ => {
+                    query_graph_node_pois(
+                        graph_node_endpoint.clone(),
+                        &ipfs_hashes,
+                        block_hash.clone(),
+                        message_block.try_into().unwrap(),
+                        my_address.clone(),
+                    )
+                    .await
+                }
+            };
+            let pois = match pois {
+                Ok(pois) => pois,
+                Err(e) => {
+                    error!("{}: {}", "Failed to query message".red(), e);
+                    continue;
+                }
+            };
 
-                        match GRAPHCAST_AGENT
-                            .get()
-                            .unwrap()
-                            .send_message(
-                                id.clone(),
-                                network_name,
-                                message_block,
-                                Some(radio_message),
-                            )
-                            .await
-                        {
-                            Ok(sent) => {
-                                info!("{}: {}", "Sent message id".green(), sent);
-                            }
-                            Err(e) => error!("{}: {}", "Failed to send message".red(), e),
-                        };
+            for (id, network_name) in deployments {
+                let content = match pois.get(&id) {
+                    Some(content) => content.clone(),
+                    None => {
+                        error!("{}: {}", "Graph node returned no POI for".red(), id);
+                        continue;
                     }
-                    Err(e) => error!("{}: {}", "Failed to query message".red(), e),
+                };
+                let attestation = Attestation {
+                    npoi: content.clone(),
+                    stake_weight: my_stake.clone(),
+                    senders: Vec::new(),
+                };
+
+                save_local_attestation(
+                    &mut *local_attestations.lock().await,
+                    attestation,
+                    id.clone(),
+                    message_block,
+                );
+
+                // Observers keep our NPOI around to compare against, but never send it
+                if config.observer {
+                    continue;
+                }
+
+                // Only a v2 payload can name a chain registered through RADIO_NETWORKS, which the SDK sends as unknown
+                let (sdk_network, chain) = network_name.to_wire();
+                let sent = if config.payload_version == RadioPayloadMessageV2::VERSION
+                    || chain.is_some()
+                {
+                    let radio_message = RadioPayloadMessageV2::new(
+                        id.clone(),
+                        content.clone(),
+                        block_hash.clone(),
+                        MOCK_GRAPH_NODE_VERSION.to_string(),
+                        MOCK_POI_ALGORITHM.to_string(),
+                    )
+                    .with_network(chain.unwrap_or_default())
+                    .with_sent_at(Utc::now().timestamp_millis());
+                    send_payload(&id, sdk_network, message_block, radio_message).await
+                } else {
+                    let radio_message = RadioPayloadMessage::new(id.clone(), content.clone());
+                    send_payload(&id, sdk_network, message_block, radio_message).await
+                };
+
+                if sent.is_ok() {
+                    SENT_MESSAGES
+                        .get()
+                        .unwrap()
+                        .lock()
+                        .unwrap()
+                        .push(SentMessage {
+                            identifier: id.clone(),
+                            network: network_name.clone(),
+                            block_number: message_block,
+                            npoi: content.clone(),
+                        });
                 }
             }
         }
@@ -406,16 +885,15 @@ where
             block_number = 0;
             MESSAGES.get().unwrap().lock().unwrap().clear()
         }
+        round += 1;
         setup_mock_server(
             block_number,
+            round,
             &indexer_address,
             &graphcast_id,
-            &config.subgraphs.clone().unwrap_or(vec![
-                MOCK_SUBGRAPH_MAINNET.to_string(),
-                MOCK_SUBGRAPH_GOERLI.to_string(),
-            ]),
-            &config.indexer_stake,
-            &config.poi,
+            &allocations_at(&subgraphs, &config.allocation_schedule, block_number),
+            &deployments,
+            config,
         )
         .await;
         sleep(Duration::from_secs(5));
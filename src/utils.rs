@@ -1,12 +1,44 @@
+use crate::notifiers::NotifierConfig;
+use crate::rejections::{record_observed, record_rejection, Rejection};
 use crate::RadioPayloadMessage;
+use ethers::signers::{LocalWallet, Signer};
 use graphcast_sdk::graphcast_agent::message_typing::GraphcastMessage;
+use once_cell::sync::Lazy;
 use rand::{thread_rng, Rng};
+use regex::Regex;
 use secp256k1::SecretKey;
+use serde_json::{json, Value};
 use sha3::{Digest, Keccak256};
-use std::{env, net::TcpListener};
-use tracing::{debug, error, info};
+use std::{
+    collections::HashMap,
+    env,
+    net::TcpListener,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex as SyncMutex,
+    },
+    time::Duration,
+};
+use tracing::{debug, info};
 use wiremock::matchers::{method, path};
-use wiremock::{Mock, MockServer, ResponseTemplate};
+use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
+
+/// Block hash the mock graph node resolves every block number to
+pub const MOCK_BLOCK_HASH: &str =
+    "4dbba1ba9fb18b0034965712598be1368edcf91ae2c551d59462aab578dab9c5";
+/// POI the mock graph node answers with on and after the block set in `RadioRuntimeConfig::poi_diverges_at`
+pub const MOCK_DIVERGED_POI: &str =
+    "0x21f17ec90db38d2ad62b1f98a8707654ec6fc1fe2a2538775f5afdf756243eb6";
+/// 100000 GRT, what the mock network subgraph reports as `minimumIndexerStake` unless told otherwise
+pub const MOCK_MINIMUM_INDEXER_STAKE: &str = "100000000000000000000000";
+/// Hash of every block the mock graph node reports as indexed
+const MOCK_CHAINHEAD_HASH: &str =
+    "b30395958a317ccc06da46782f660ce674cbe6792e5573dc630978c506114a0a";
+
+/// Matches the alias and subgraph variable of each `proofOfIndexing` field in a batched POI query
+static POI_ALIAS_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(\w+)\s*:\s*proofOfIndexing\(\s*subgraph\s*:\s*\$(\w+)").unwrap());
 
 pub fn generate_random_address() -> String {
     let mut rng = thread_rng();
@@ -27,13 +59,17 @@ pub fn generate_random_address() -> String {
 
 pub fn empty_attestation_handler(
 ) -> impl Fn(Result<GraphcastMessage<RadioPayloadMessage>, anyhow::Error>) {
-    |msg: Result<GraphcastMessage<RadioPayloadMessage>, anyhow::Error>| match msg {
-        Ok(msg) => {
-            debug!("Message received: {:?}", msg);
-            debug!("This is a setup instance. Continuing...");
-        }
-        Err(err) => {
-            error!("{}", err);
+    |msg: Result<GraphcastMessage<RadioPayloadMessage>, anyhow::Error>| {
+        record_observed();
+
+        match msg {
+            Ok(msg) => {
+                debug!("Message received: {:?}", msg);
+                debug!("This is a setup instance. Continuing...");
+            }
+            Err(err) => {
+                record_rejection(Rejection::from_error(&err));
+            }
         }
     }
 }
@@ -46,119 +82,555 @@ pub fn get_random_port() -> String {
     port
 }
 
+/// A deployment as the mock graph node reports it in its indexing statuses
+#[derive(Clone, Debug)]
+pub struct MockDeployment {
+    pub ipfs_hash: String,
+    pub network: String,
+    /// How far ahead of the mock block number this deployment's chain is
+    pub block_offset: u64,
+    /// Further chains the deployment indexes, with their own block offsets
+    pub extra_chains: Vec<(String, u64)>,
+    /// How many blocks the deployment's latest indexed block trails its chains' heads
+    pub lag: u64,
+    pub synced: bool,
+    pub health: String,
+    pub fatal_error: Option<String>,
+}
+
+impl MockDeployment {
+    pub fn new(ipfs_hash: &str, network: &str, block_offset: u64) -> Self {
+        MockDeployment {
+            ipfs_hash: ipfs_hash.to_string(),
+            network: network.to_string(),
+            block_offset,
+            extra_chains: Vec::new(),
+            lag: 0,
+            synced: true,
+            health: "healthy".to_string(),
+            fatal_error: None,
+        }
+    }
+
+    /// Makes the deployment index another chain, reported after the ones it already indexes
+    pub fn with_chain(mut self, network: &str, block_offset: u64) -> Self {
+        self.extra_chains.push((network.to_string(), block_offset));
+        self
+    }
+
+    /// Keeps the deployment's latest indexed block the given number of blocks behind the chainhead
+    pub fn lagging(mut self, blocks: u64) -> Self {
+        self.lag = blocks;
+        self
+    }
+
+    /// The latest block the deployment has indexed on its first chain, for a given mock block number
+    pub fn indexed_head(&self, block_number: u64) -> u64 {
+        (block_number + self.block_offset).saturating_sub(self.lag)
+    }
+
+    /// Reports the deployment the way graph node does once it has hit a deterministic error
+    pub fn failed(mut self, error: &str) -> Self {
+        self.synced = false;
+        self.health = "failed".to_string();
+        self.fatal_error = Some(error.to_string());
+        self
+    }
+
+    /// The first deployment indexes mainnet, the rest index goerli 5 blocks ahead
+    pub fn defaults(ipfs_hashes: &[String]) -> Vec<Self> {
+        ipfs_hashes
+            .iter()
+            .enumerate()
+            .map(|(i, ipfs_hash)| match i {
+                0 => MockDeployment::new(ipfs_hash, "mainnet", 0),
+                _ => MockDeployment::new(ipfs_hash, "goerli", 5),
+            })
+            .collect()
+    }
+}
+
+/// Allocations the mock network subgraph opens and closes once the mock block number reaches `at_block`
+#[derive(Clone, Debug)]
+pub struct MockAllocationChange {
+    pub at_block: u64,
+    pub open: Vec<String>,
+    pub close: Vec<String>,
+}
+
+impl MockAllocationChange {
+    pub fn new(at_block: u64) -> Self {
+        MockAllocationChange {
+            at_block,
+            open: Vec::new(),
+            close: Vec::new(),
+        }
+    }
+
+    pub fn opening(mut self, ipfs_hash: &str) -> Self {
+        self.open.push(ipfs_hash.to_string());
+        self
+    }
+
+    pub fn closing(mut self, ipfs_hash: &str) -> Self {
+        self.close.push(ipfs_hash.to_string());
+        self
+    }
+}
+
+/// The deployments allocated to at a mock block number, starting from `initial` and applying every change due by
+/// then in order. The mock block number wraps around, which brings the initial allocations back
+pub fn allocations_at(
+    initial: &[String],
+    schedule: &[MockAllocationChange],
+    block_number: u64,
+) -> Vec<String> {
+    let mut allocations = initial.to_vec();
+    for change in schedule
+        .iter()
+        .filter(|change| change.at_block <= block_number)
+    {
+        allocations.retain(|ipfs_hash| !change.close.contains(ipfs_hash));
+        for ipfs_hash in &change.open {
+            if !allocations.contains(ipfs_hash) {
+                allocations.push(ipfs_hash.clone());
+            }
+        }
+    }
+
+    allocations
+}
+
+/// Every deployment allocated to at some point of a schedule, in the order they are first allocated
+pub fn scheduled_deployments(initial: &[String], schedule: &[MockAllocationChange]) -> Vec<String> {
+    let mut deployments = initial.to_vec();
+    for ipfs_hash in schedule.iter().flat_map(|change| &change.open) {
+        if !deployments.contains(ipfs_hash) {
+            deployments.push(ipfs_hash.clone());
+        }
+    }
+
+    deployments
+}
+
+/// A simulated Indexer, sending attestations signed with its own generated key. The mock registry resolves its
+/// graphcast ID to its own Indexer address and the mock network subgraph reports its own stake
+#[derive(Clone, Debug)]
+pub struct MockSender {
+    pub wallet: LocalWallet,
+    pub indexer_address: String,
+    pub stake: String,
+}
+
+impl MockSender {
+    pub fn generate(stake: String) -> Self {
+        MockSender {
+            wallet: LocalWallet::new(&mut thread_rng()),
+            indexer_address: generate_random_address(),
+            stake,
+        }
+    }
+
+    pub fn graphcast_id(&self) -> String {
+        format!("{:#x}", self.wallet.address())
+    }
+
+    /// The same Indexer behind a freshly generated operator key
+    pub fn rotated(&self) -> Self {
+        MockSender {
+            wallet: LocalWallet::new(&mut thread_rng()),
+            ..self.clone()
+        }
+    }
+}
+
+/// The registry moving a simulated sender's Indexer over to a new operator key once the mock block number reaches
+/// `at_block`. Neither key is registered while the other one is
+#[derive(Clone, Debug)]
+pub struct MockOperatorRotation {
+    pub at_block: u64,
+    pub from: MockSender,
+    pub to: MockSender,
+}
+
+impl MockOperatorRotation {
+    pub fn new(at_block: u64, from: &MockSender) -> Self {
+        MockOperatorRotation {
+            at_block,
+            from: from.clone(),
+            to: from.rotated(),
+        }
+    }
+}
+
+/// The simulated senders the registry resolves at a mock block number, and the operator keys it doesn't resolve at
+/// all, either retired already or not taken up yet
+fn registry_at(
+    senders: &[MockSender],
+    rotations: &[MockOperatorRotation],
+    block_number: u64,
+) -> (Vec<MockSender>, Vec<MockSender>) {
+    let mut registered = senders.to_vec();
+    let mut unregistered = Vec::new();
+    for rotation in rotations {
+        let (old, new) = if rotation.at_block <= block_number {
+            (&rotation.from, &rotation.to)
+        } else {
+            (&rotation.to, &rotation.from)
+        };
+        registered.retain(|sender| sender.graphcast_id() != old.graphcast_id());
+        if !registered
+            .iter()
+            .any(|sender| sender.graphcast_id() == new.graphcast_id())
+        {
+            registered.push(new.clone());
+        }
+        unregistered.push(old.clone());
+    }
+
+    (registered, unregistered)
+}
+
+/// Lowercased hex of an address, for finding it in a request however the query spells it
+fn address_hex(address: &str) -> String {
+    address.trim_start_matches("0x").to_lowercase()
+}
+
+#[derive(Clone, Debug)]
+pub enum FaultKind {
+    /// Graph node answers with a 503
+    Unavailable,
+    /// Graph node answers normally, but only after the given delay
+    Slow(Duration),
+}
+
+/// Makes the mock graph node misbehave for a number of requests. Faults in a config are served in order,
+/// and keep counting across mock server remounts so an outage can outlast the block it started on
+#[derive(Clone, Debug)]
+pub struct MockFault {
+    pub kind: FaultKind,
+    pub requests: u64,
+}
+
+impl MockFault {
+    pub fn new(kind: FaultKind, requests: u64) -> Self {
+        MockFault { kind, requests }
+    }
+}
+
+/// Number of requests the mock graph node has served a fault for so far
+static FAULTED_REQUESTS: AtomicU64 = AtomicU64::new(0);
+
+pub fn faulted_requests() -> u64 {
+    FAULTED_REQUESTS.load(Ordering::SeqCst)
+}
+
+/// Number of POIs the mock graph node was asked for on blocks the deployment hasn't indexed yet
+static UNINDEXED_POI_REQUESTS: AtomicU64 = AtomicU64::new(0);
+
+pub fn unindexed_poi_requests() -> u64 {
+    UNINDEXED_POI_REQUESTS.load(Ordering::SeqCst)
+}
+
+/// Number of requests asking for POIs the mock graph node has served per block, however many POIs each asked for
+static POI_REQUESTS: Lazy<SyncMutex<HashMap<u64, u64>>> = Lazy::new(Default::default);
+
+pub fn poi_requests(block_number: u64) -> u64 {
+    POI_REQUESTS
+        .lock()
+        .unwrap()
+        .get(&block_number)
+        .copied()
+        .unwrap_or_default()
+}
+
+/// Answers every graph node query the radio makes. POI fields are answered per alias, so a batched query gets a
+/// POI for each deployment in it, and only when the POI was asked for without an indexer or for the mocked indexer
+/// on a block the deployment has already indexed
+struct GraphNodeResponder {
+    poi: String,
+    indexer_address: String,
+    indexing_statuses: Vec<Value>,
+    /// Latest indexed block of each deployment on its first chain
+    indexed_heads: HashMap<String, u64>,
+    poi_diverges_at: Option<u64>,
+    faults: Vec<MockFault>,
+}
+
+impl GraphNodeResponder {
+    /// Claims the next faulty response, if the configured faults haven't all been served yet
+    fn next_fault(&self) -> Option<FaultKind> {
+        let total: u64 = self.faults.iter().map(|fault| fault.requests).sum();
+        let mut served = FAULTED_REQUESTS
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |served| {
+                (served < total).then_some(served + 1)
+            })
+            .ok()?;
+
+        self.faults.iter().find_map(|fault| {
+            if served < fault.requests {
+                Some(fault.kind.clone())
+            } else {
+                served -= fault.requests;
+                None
+            }
+        })
+    }
+}
+
+impl Respond for GraphNodeResponder {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        let fault = self.next_fault();
+        if let Some(FaultKind::Unavailable) = fault {
+            return ResponseTemplate::new(503);
+        }
+
+        let body: Value = serde_json::from_slice(&request.body).unwrap_or_default();
+        let query = body["query"].as_str().unwrap_or_default();
+
+        let variables = &body["variables"];
+        if let (true, Some(block_number)) = (
+            query.contains("proofOfIndexing"),
+            variables["blockNumber"].as_u64(),
+        ) {
+            *POI_REQUESTS
+                .lock()
+                .unwrap()
+                .entry(block_number)
+                .or_default() += 1;
+        }
+
+        let poi = |subgraph: &Value| {
+            if let Some(indexer) = variables["indexer"].as_str() {
+                if !indexer.eq_ignore_ascii_case(&self.indexer_address) {
+                    return Value::Null;
+                }
+            }
+
+            // Like graph node, there is no POI for a block the deployment hasn't indexed yet
+            let head = subgraph.as_str().and_then(|s| self.indexed_heads.get(s));
+            let block_number = variables["blockNumber"].as_u64();
+            match (block_number, head) {
+                (Some(block_number), Some(head)) if block_number > *head => {
+                    UNINDEXED_POI_REQUESTS.fetch_add(1, Ordering::SeqCst);
+                    return Value::Null;
+                }
+                _ => {}
+            }
+
+            match (block_number, self.poi_diverges_at) {
+                (Some(block_number), Some(diverges_at)) if block_number >= diverges_at => {
+                    json!(MOCK_DIVERGED_POI)
+                }
+                _ => json!(self.poi),
+            }
+        };
+
+        let mut data = json!({
+            "proofOfIndexing": poi(&variables["subgraph"]),
+            "blockHashFromNumber": MOCK_BLOCK_HASH,
+            "indexingStatuses": self.indexing_statuses,
+        });
+        for alias in POI_ALIAS_REGEX.captures_iter(query) {
+            data[&alias[1]] = poi(&variables[&alias[2]]);
+        }
+
+        let response = ResponseTemplate::new(200).set_body_json(json!({ "data": data }));
+        match fault {
+            Some(FaultKind::Slow(delay)) => response.set_delay(delay),
+            _ => response,
+        }
+    }
+}
+
+/// Resolves the graphcast ID of each simulated sender to its own Indexer, unregistered operator keys to no Indexer
+/// and any other graphcast ID to the mocked Indexer
+struct RegistryResponder {
+    graphcast_id: String,
+    indexer_address: String,
+    senders: Vec<MockSender>,
+    unregistered: Vec<MockSender>,
+}
+
+impl Respond for RegistryResponder {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        let body = String::from_utf8_lossy(&request.body).to_lowercase();
+        if self
+            .unregistered
+            .iter()
+            .any(|sender| body.contains(&address_hex(&sender.graphcast_id())))
+        {
+            return ResponseTemplate::new(200).set_body_json(json!({
+                "data": { "indexers": [] },
+                "errors": null,
+                "extensions": null,
+            }));
+        }
+
+        let (graphcast_id, indexer_address) = self
+            .senders
+            .iter()
+            .find(|sender| body.contains(&address_hex(&sender.graphcast_id())))
+            .map(|sender| (sender.graphcast_id(), sender.indexer_address.clone()))
+            .unwrap_or_else(|| (self.graphcast_id.clone(), self.indexer_address.clone()));
+
+        ResponseTemplate::new(200).set_body_json(json!({
+            "data": {
+                "indexers": [
+                    {
+                        "graphcastID": graphcast_id,
+                        "id": indexer_address,
+                    }
+                ]
+            },
+            "errors": null,
+            "extensions": null,
+        }))
+    }
+}
+
+/// Reports the stake of each simulated sender's Indexer, and `staked_tokens` for any other Indexer
+struct NetworkSubgraphResponder {
+    staked_tokens: String,
+    minimum_indexer_stake: String,
+    allocations: Vec<Value>,
+    senders: Vec<MockSender>,
+}
+
+impl Respond for NetworkSubgraphResponder {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        let body = String::from_utf8_lossy(&request.body).to_lowercase();
+        let staked_tokens = self
+            .senders
+            .iter()
+            .find(|sender| body.contains(&address_hex(&sender.indexer_address)))
+            .map(|sender| &sender.stake)
+            .unwrap_or(&self.staked_tokens);
+
+        ResponseTemplate::new(200).set_body_json(json!({
+            "data": {
+                "indexer": {
+                    "stakedTokens": staked_tokens,
+                    "allocations": self.allocations,
+                },
+                "graphNetwork": {
+                    "minimumIndexerStake": self.minimum_indexer_stake,
+                },
+            },
+            "errors": null,
+        }))
+    }
+}
+
+/// The `minimumIndexerStake` in effect after a number of rounds, the last change due by then or the configured one
+fn minimum_indexer_stake_at(config: &RadioRuntimeConfig, round: u64) -> String {
+    config
+        .minimum_stake_changes
+        .iter()
+        .filter(|(at_round, _)| *at_round <= round)
+        .max_by_key(|(at_round, _)| *at_round)
+        .map_or(&config.minimum_indexer_stake, |(_, minimum)| minimum)
+        .clone()
+}
+
+/// `round` counts the rounds of the radio loop so far, unlike the mock block number it never wraps around
 pub async fn setup_mock_server(
     block_number: u64,
-    indexer_address: &String,
-    graphcast_id: &String,
+    round: u64,
+    indexer_address: &str,
+    graphcast_id: &str,
     ipfs_hashes: &[String],
-    staked_tokens: &String,
-    poi: &String,
+    deployments: &[MockDeployment],
+    config: &RadioRuntimeConfig,
 ) -> String {
     let mock_server = MockServer::start().await;
+    let staked_tokens = &config.indexer_stake;
+    let (registered, unregistered) =
+        registry_at(&config.senders, &config.operator_rotations, block_number);
 
     Mock::given(method("POST"))
         .and(path("/graphcast-registry"))
-        .respond_with(ResponseTemplate::new(200).set_body_string(format!(
-            r#"{{
-                "data": {{
-                  "indexers": [
-                    {{
-                      "graphcastID": "{graphcast_id}",
-                      "id": "{indexer_address}"
-                    }}
-                  ]
-                }},
-                "errors": null,
-                "extensions": null
-              }}
-              "#,
-            graphcast_id = graphcast_id,
-            indexer_address = indexer_address,
-        )))
+        .respond_with(RegistryResponder {
+            graphcast_id: graphcast_id.to_string(),
+            indexer_address: indexer_address.to_string(),
+            senders: registered.clone(),
+            unregistered,
+        })
         .mount(&mock_server)
         .await;
 
-    let mut allocations_str = String::new();
-    for ipfs_hash in ipfs_hashes {
-        allocations_str.push_str(&format!(
-            r#"{{"subgraphDeployment": {{"ipfsHash": "{}"}}}},"#,
-            ipfs_hash
-        ));
-    }
-
     Mock::given(method("POST"))
         .and(path("/network-subgraph"))
-        .respond_with(ResponseTemplate::new(200).set_body_string(format!(
-            r#"{{
-                "data": {{
-                    "indexer" : {{
-                        "stakedTokens": "{staked_tokens}",
-                        "allocations": [{}
-                        ]
-                    }},
-                    "graphNetwork": {{
-                        "minimumIndexerStake": "100000000000000000000000"
-                    }}
-                }},
-                "errors": null
-            }}"#,
-            allocations_str.trim_end_matches(','),
-        )))
+        .respond_with(NetworkSubgraphResponder {
+            staked_tokens: staked_tokens.clone(),
+            minimum_indexer_stake: minimum_indexer_stake_at(config, round),
+            allocations: ipfs_hashes
+                .iter()
+                .map(|ipfs_hash| json!({ "subgraphDeployment": { "ipfsHash": ipfs_hash } }))
+                .collect(),
+            senders: registered,
+        })
         .mount(&mock_server)
         .await;
 
+    let indexing_statuses = deployments
+        .iter()
+        .map(|deployment| {
+            let chains = std::iter::once((&deployment.network, deployment.block_offset))
+                .chain(
+                    deployment
+                        .extra_chains
+                        .iter()
+                        .map(|(network, offset)| (network, *offset)),
+                )
+                .map(|(network, offset)| {
+                    let head = block_number + offset;
+
+                    json!({
+                        "network": network,
+                        "latestBlock": {
+                            "number": head.saturating_sub(deployment.lag).to_string(),
+                            "hash": MOCK_CHAINHEAD_HASH,
+                        },
+                        "chainHeadBlock": {
+                            "number": head.to_string(),
+                            "hash": MOCK_CHAINHEAD_HASH,
+                        },
+                    })
+                })
+                .collect::<Vec<Value>>();
+
+            json!({
+                "subgraph": deployment.ipfs_hash,
+                "synced": deployment.synced,
+                "health": deployment.health,
+                "fatalError": deployment.fatal_error.as_ref().map(|message| json!({
+                    "handler": null,
+                    "message": message,
+                })),
+                "chains": chains
+            })
+        })
+        .collect::<Vec<Value>>();
+
     Mock::given(method("POST"))
         .and(path("/graphql"))
-        .respond_with(ResponseTemplate::new(200).set_body_string(format!(
-            r#"{{
-                "data": {{
-                  "proofOfIndexing": "{poi}",
-                  "blockHashFromNumber":"4dbba1ba9fb18b0034965712598be1368edcf91ae2c551d59462aab578dab9c5",
-                  "indexingStatuses": [
-                    {{
-                      "subgraph": "{}",
-                      "synced": true,
-                      "health": "healthy",
-                      "fatalError": null,
-                      "chains": [
-                        {{
-                          "network": "mainnet",
-                          "latestBlock": {{
-                            "number": "{block_number}",
-                            "hash": "b30395958a317ccc06da46782f660ce674cbe6792e5573dc630978c506114a0a"
-                          }},
-                          "chainHeadBlock": {{
-                            "number": "{block_number}",
-                            "hash": "b30395958a317ccc06da46782f660ce674cbe6792e5573dc630978c506114a0a"
-                          }}
-                        }}
-                      ]
-                    }},
-                    {{
-                        "subgraph": "{}",
-                        "synced": true,
-                        "health": "healthy",
-                        "fatalError": null,
-                        "chains": [
-                          {{
-                            "network": "goerli",
-                            "latestBlock": {{
-                                "number": "{}",
-                                "hash": "b30395958a317ccc06da46782f660ce674cbe6792e5573dc630978c506114a0a"
-                              }},
-                              "chainHeadBlock": {{
-                                "number": "{}",
-                                "hash": "b30395958a317ccc06da46782f660ce674cbe6792e5573dc630978c506114a0a"
-                              }}
-                          }}
-                        ]
-                      }}
-                  ]
-                }}
-              }}
-              "#,
-              ipfs_hashes[0], ipfs_hashes[1], block_number + 5, block_number + 5, // use the provided ipfs hashes
-            )))
+        .respond_with(GraphNodeResponder {
+            poi: config.poi.clone(),
+            indexer_address: indexer_address.to_string(),
+            indexing_statuses,
+            indexed_heads: deployments
+                .iter()
+                .map(|deployment| {
+                    (
+                        deployment.ipfs_hash.clone(),
+                        deployment.indexed_head(block_number),
+                    )
+                })
+                .collect(),
+            poi_diverges_at: config.poi_diverges_at,
+            faults: config.graph_node_faults.clone(),
+        })
         .mount(&mock_server)
         .await;
 
@@ -186,11 +658,37 @@ pub struct RadioRuntimeConfig {
     pub is_setup_instance: bool,
     pub panic_if_poi_diverged: bool,
     pub subgraphs: Option<Vec<String>>,
+    /// What the mock graph node reports as indexed, defaults to `MockDeployment::defaults` of the subgraphs
+    pub deployments: Option<Vec<MockDeployment>>,
     pub indexer_stake: String,
     pub poi: String,
     pub indexer_address: Option<String>,
     pub operator_address: Option<String>,
     pub invalid_payload: bool,
+    /// Receive and compare, without ever sending an attestation
+    pub observer: bool,
+    pub payload_version: u32,
+    pub graph_node_faults: Vec<MockFault>,
+    /// Block from which the mock graph node answers POI queries with `MOCK_DIVERGED_POI` instead of `poi`
+    pub poi_diverges_at: Option<u64>,
+    /// Told about comparison outcomes, on top of the ones configured through the environment
+    pub notifiers: Vec<NotifierConfig>,
+    /// Where the run's report is written when it ends, `RADIO_REPORT_PATH` is used if this isn't set
+    pub report_path: Option<PathBuf>,
+    /// Simulated senders registered in the mock registry, next to the mocked Indexer
+    pub senders: Vec<MockSender>,
+    /// Allocations the mock network subgraph opens and closes as the mock block number advances, on top of the
+    /// subgraphs allocated to from the start
+    pub allocation_schedule: Vec<MockAllocationChange>,
+    /// Simulated senders the mock registry moves over to a new operator key as the mock block number advances
+    pub operator_rotations: Vec<MockOperatorRotation>,
+    /// What the mock network subgraph reports as `minimumIndexerStake`
+    pub minimum_indexer_stake: String,
+    /// Rounds of the radio loop from which the minimum stake changes, and what it changes to. Keyed to rounds rather
+    /// than the mock block number, so a change stays in effect once the block number wraps around
+    pub minimum_stake_changes: Vec<(u64, String)>,
+    /// Hex encoded key the agent signs its messages with, a random one is generated for every run if this isn't set
+    pub private_key: Option<String>,
 }
 
 impl RadioRuntimeConfig {
@@ -199,11 +697,24 @@ impl RadioRuntimeConfig {
             is_setup_instance: true,
             panic_if_poi_diverged: false,
             subgraphs: None,
+            deployments: None,
             indexer_stake: "100000000000000000000000".to_string(),
             poi: "0x25331f98b82ca7f3966256bf508a7ede52e715b631dfa3d73b846bb7617f6b9e".to_string(),
             indexer_address: None,
             operator_address: None,
             invalid_payload: false,
+            observer: false,
+            payload_version: 1,
+            graph_node_faults: vec![],
+            poi_diverges_at: None,
+            notifiers: vec![],
+            report_path: None,
+            senders: vec![],
+            allocation_schedule: vec![],
+            operator_rotations: vec![],
+            minimum_indexer_stake: MOCK_MINIMUM_INDEXER_STAKE.to_string(),
+            minimum_stake_changes: vec![],
+            private_key: None,
         }
     }
     pub fn new(is_setup_instance: bool, panic_if_poi_diverged: bool) -> Self {
@@ -211,11 +722,24 @@ impl RadioRuntimeConfig {
             is_setup_instance,
             panic_if_poi_diverged,
             subgraphs: None,
+            deployments: None,
             indexer_stake: "100000000000000000000000".to_string(),
             poi: "0x25331f98b82ca7f3966256bf508a7ede52e715b631dfa3d73b846bb7617f6b9e".to_string(),
             indexer_address: None,
             operator_address: None,
             invalid_payload: false,
+            observer: false,
+            payload_version: 1,
+            graph_node_faults: vec![],
+            poi_diverges_at: None,
+            notifiers: vec![],
+            report_path: None,
+            senders: vec![],
+            allocation_schedule: vec![],
+            operator_rotations: vec![],
+            minimum_indexer_stake: MOCK_MINIMUM_INDEXER_STAKE.to_string(),
+            minimum_stake_changes: vec![],
+            private_key: None,
         }
     }
 }